@@ -29,6 +29,10 @@ impl<'a> ToTokens for Boolean<'a> {
             impl crate::jstation::data::ParameterSetter for #param {
                 type Parameter = Self;
 
+                fn get(&self, _param: Self) -> Self {
+                    *self
+                }
+
                 fn set(&mut self, new: Self) -> Option<Self> {
                     if *self == new {
                         return None;
@@ -101,6 +105,25 @@ impl<'a> ToTokens for Boolean<'a> {
                         data.buf_mut()[PARAM_NB.as_usize()] = self.raw_value();
                     }
                 }
+
+                impl crate::jstation::data::TextParameter for #param {
+                    fn to_text(self) -> Option<crate::jstation::data::TextValue> {
+                        Some(crate::jstation::data::TextValue::Bool(self.0))
+                    }
+
+                    fn set_text(
+                        &mut self,
+                        value: &crate::jstation::data::TextValue,
+                    ) -> Result<(), crate::jstation::Error> {
+                        match value {
+                            crate::jstation::data::TextValue::Bool(value) => {
+                                self.0 = *value;
+                                Ok(())
+                            }
+                            other => Err(other.unexpected(#param_name)),
+                        }
+                    }
+                }
             });
         }
 
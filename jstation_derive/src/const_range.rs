@@ -18,6 +18,7 @@ pub struct ConstRange<'a> {
     is_discr: bool,
     default_pos: DefaultPos,
     displays: Vec<Display>,
+    text_map: Option<Ident>,
     min: Option<u8>,
     max: Option<u8>,
 }
@@ -29,6 +30,7 @@ impl<'a> ConstRange<'a> {
             is_discr: false,
             default_pos: DefaultPos::default(),
             displays: Vec::new(),
+            text_map: None,
             min: None,
             max: None,
         };
@@ -62,26 +64,12 @@ impl<'a> ConstRange<'a> {
                 self.displays.push(Display::Raw);
             }
             "display_map" => {
-                let list_expr = arg.value_or_abort(self.base.field);
-                let path = match list_expr {
-                    Expr::Path(expr_path) => expr_path.path,
-                    _ => panic!(
-                        "Field {}: unexpected `display_map` expression",
-                        self.base.field.to_token_stream(),
-                    ),
-                };
-
-                let name = match path.get_ident() {
-                    Some(name) => name,
-                    None => {
-                        panic!(
-                            "Field {}: expecting ident for `display_map`",
-                            self.base.field.to_token_stream(),
-                        )
-                    }
-                };
-
-                self.displays.push(Display::Map(name.clone()));
+                let name = self.map_name_or_abort(arg, "display_map");
+                self.displays.push(Display::Map(name));
+            }
+            "text_map" => {
+                let name = self.map_name_or_abort(arg, "text_map");
+                self.text_map = Some(name);
             }
             "discriminant" => {
                 arg.no_value_or_abort(self.base.field);
@@ -94,6 +82,98 @@ impl<'a> ConstRange<'a> {
     pub fn is_discriminant(&self) -> bool {
         self.is_discr
     }
+
+    fn map_name_or_abort(&self, arg: Arg, attr: &str) -> Ident {
+        let path = match arg.value_or_abort(self.base.field) {
+            Expr::Path(expr_path) => expr_path.path,
+            _ => panic!(
+                "Field {}: unexpected `{attr}` expression",
+                self.base.field.to_token_stream(),
+            ),
+        };
+
+        match path.get_ident() {
+            Some(name) => name.clone(),
+            None => panic!(
+                "Field {}: expecting ident for `{attr}`",
+                self.base.field.to_token_stream(),
+            ),
+        }
+    }
+
+    /// Returns the `display_map` used for text representations, if any.
+    ///
+    /// This is the `text_map` if declared, or else the only `display_map`.
+    fn text_map(&self) -> Option<&Ident> {
+        let mut maps = self.displays.iter().filter_map(|display| match display {
+            Display::Map(name) => Some(name),
+            _ => None,
+        });
+
+        if let Some(text_map) = self.text_map.as_ref() {
+            if !maps.any(|name| name == text_map) {
+                panic!(
+                    "Field {}: `text_map` {text_map} is not a `display_map`",
+                    self.base.field.to_token_stream(),
+                );
+            }
+
+            return Some(text_map);
+        }
+
+        let name = maps.next()?;
+        if maps.next().is_some() {
+            panic!(
+                "Field {}: several `display_map`s, use `text_map` to select the one for text",
+                self.base.field.to_token_stream(),
+            );
+        }
+
+        Some(name)
+    }
+
+    /// Returns the tokens which format the param `value` for display.
+    ///
+    /// Params with a `display_map` are displayed using their `text_map`.
+    pub fn display_value(&self, value: TokenStream) -> TokenStream {
+        match self.text_map() {
+            Some(name) => {
+                let MapIdents { name_method, .. } = self.map_idents(name);
+                quote! { #value.#name_method().to_string() }
+            }
+            None => quote! { #value.to_string() },
+        }
+    }
+
+    /// Returns the idents for the named param struct & the methods of a `display_map`.
+    fn map_idents(&self, name: &Ident) -> MapIdents {
+        use heck::{ToSnakeCase, ToUpperCamelCase};
+
+        let param = &self.base.field.ty;
+        let name_str = name.to_string();
+
+        let name_as_type = name_str.to_upper_camel_case();
+        let named_param_str = format!("{}{}", param.to_token_stream(), name_as_type);
+        let named_param = Ident::new(&named_param_str, self.base.field.span());
+
+        let name_as_field = name_str.to_snake_case();
+        let name_method = Ident::new(&name_as_field, self.base.field.span());
+        let names_method = Ident::new(format!("{name_as_field}s").as_str(), self.base.field.span());
+
+        MapIdents {
+            named_param_str,
+            named_param,
+            name_method,
+            names_method,
+        }
+    }
+}
+
+struct MapIdents {
+    named_param_str: String,
+    named_param: Ident,
+    name_method: Ident,
+    names_method: Ident,
 }
 
 impl<'a> ToTokens for ConstRange<'a> {
@@ -185,6 +265,10 @@ impl<'a> ToTokens for ConstRange<'a> {
             impl crate::jstation::data::ParameterSetter for #param {
                 type Parameter = Self;
 
+                fn get(&self, _param: Self) -> Self {
+                    *self
+                }
+
                 fn set(&mut self, new: Self) -> Option<Self> {
                     if self.0 == new.0 {
                         return None;
@@ -242,6 +326,61 @@ impl<'a> ToTokens for ConstRange<'a> {
                     }
                 }
             });
+
+            let (to_text, str_arm) = match self.text_map() {
+                Some(name) => {
+                    let MapIdents {
+                        name_method,
+                        names_method,
+                        ..
+                    } = self.map_idents(name);
+
+                    (
+                        quote! {
+                            Some(crate::jstation::data::TextValue::Str(
+                                self.#name_method().name.to_string(),
+                            ))
+                        },
+                        quote! {
+                            crate::jstation::data::TextValue::Str(name) => {
+                                Self::#names_method()
+                                    .iter()
+                                    .find(|named| named.name == name)
+                                    .ok_or_else(|| value.unexpected(#param_name))?
+                                    .param
+                            }
+                        },
+                    )
+                }
+                None => (
+                    quote! {
+                        Some(crate::jstation::data::TextValue::Int(self.0.as_u8().into()))
+                    },
+                    quote! {},
+                ),
+            };
+
+            tokens.extend(quote! {
+                impl crate::jstation::data::TextParameter for #param {
+                    fn to_text(self) -> Option<crate::jstation::data::TextValue> {
+                        #to_text
+                    }
+
+                    fn set_text(
+                        &mut self,
+                        value: &crate::jstation::data::TextValue,
+                    ) -> Result<(), crate::jstation::Error> {
+                        use crate::jstation::data::ConstRangeParameter;
+
+                        *self = match value {
+                            #str_arm
+                            _ => Self::try_from_raw(value.try_to_raw(#param_name)?)?,
+                        };
+
+                        Ok(())
+                    }
+                }
+            });
         }
 
         if let Some(cc_nb) = &self.base.cc_nb {
@@ -300,25 +439,21 @@ impl<'a> ToTokens for ConstRange<'a> {
                     }
                 }),
                 Display::Map(name) => {
-                    use heck::{ToShoutySnakeCase, ToSnakeCase, ToUpperCamelCase};
-
-                    let name_str = name.to_string();
+                    use heck::ToShoutySnakeCase;
 
-                    let name_as_type = name_str.to_upper_camel_case();
-                    let names_param_str = &format!("{}{}", param.to_token_stream(), name_as_type);
-                    let named_param = Ident::new(names_param_str, self.base.field.span());
+                    let MapIdents {
+                        named_param_str,
+                        named_param,
+                        name_method,
+                        names_method,
+                    } = self.map_idents(name);
 
                     let named_list = Ident::new(
-                        format!("{}S", &names_param_str.to_shouty_snake_case()).as_str(),
+                        format!("{}S", &named_param_str.to_shouty_snake_case()).as_str(),
                         self.base.field.span(),
                     );
                     let expected_list_len = (param_max - param_min) as usize + 1;
 
-                    let name_as_field = name_str.to_snake_case();
-                    let name_method = Ident::new(&name_as_field, self.base.field.span());
-                    let names_method =
-                        Ident::new(format!("{name_as_field}s").as_str(), self.base.field.span());
-
                     tokens.extend(quote! {
                         #[derive(Clone, Copy, Debug)]
                         pub struct #named_param {
@@ -2,7 +2,7 @@ use core::panic;
 
 use heck::ToTitleCase;
 use proc_macro2::TokenStream;
-use quote::ToTokens;
+use quote::{quote, ToTokens};
 use syn::{
     self,
     parse::{Parse, ParseStream},
@@ -85,6 +85,21 @@ impl<'a> Param<'a> {
         self.base().param_nb
     }
 
+    pub fn name(&self) -> &str {
+        self.base().name()
+    }
+
+    /// Returns the tokens which format the param `value` for display.
+    pub fn display_value(&self, value: TokenStream) -> TokenStream {
+        match self {
+            Param::Boolean(_) => quote! {
+                if bool::from(#value) { "On" } else { "Off" }.to_string()
+            },
+            Param::ConstRange(param) => param.display_value(value),
+            Param::VariableRange(_) => quote! { #value.to_string() },
+        }
+    }
+
     pub fn is_discriminant(&self) -> bool {
         match self {
             Param::ConstRange(param) => param.is_discriminant(),
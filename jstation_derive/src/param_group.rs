@@ -1,3 +1,4 @@
+use heck::ToTitleCase;
 use proc_macro2::TokenStream;
 use quote::{quote, ToTokens};
 use syn::{Data, DataStruct, DeriveInput, Fields, Ident};
@@ -66,6 +67,9 @@ impl<'a> ToTokens for ParamGroup<'a> {
         tokens.extend({
             let param_enum = self.params.iter().map(Param::ty);
             let param_from = self.params.iter().map(Param::ty);
+            let param_variant = self.params.iter().map(Param::ty);
+            let param_name = self.params.iter().map(Param::name);
+            let block = group_name.to_string().to_title_case();
 
             quote! {
                 #[derive(Clone, Copy, Debug)]
@@ -73,6 +77,15 @@ impl<'a> ToTokens for ParamGroup<'a> {
                     #( #param_enum(#param_enum), )*
                 }
 
+                impl Parameter {
+                    /// Returns the block & the name of the param for display.
+                    pub fn names(self) -> (&'static str, &'static str) {
+                        match self {
+                            #( Parameter::#param_variant(_) => (#block, #param_name), )*
+                        }
+                    }
+                }
+
                 impl From<Parameter> for crate::jstation::dsp::Parameter {
                     fn from(param: Parameter) -> Self {
                         crate::jstation::dsp::Parameter::#group_name(param)
@@ -146,10 +159,28 @@ impl<'a> ToTokens for ParamGroup<'a> {
                 }
             });
 
+            let variant_get_field = self.params.iter().map(|p| {
+                let variant = p.ty();
+                let field = p.field();
+
+                quote! {
+                    Parameter::#variant(param) => {
+                        Parameter::#variant(ParameterSetter::get(&self.#field, param))
+                    }
+                }
+            });
+
             quote! {
                 impl crate::jstation::data::ParameterSetter for #group_name {
                     type Parameter = Parameter;
 
+                    fn get(&self, param: Parameter) -> Parameter {
+                        use crate::jstation::data::ParameterSetter;
+                        match param {
+                            #( #variant_get_field )*
+                        }
+                    }
+
                     fn set(&mut self, param: Parameter) -> Option<Parameter> {
                         use crate::jstation::data::ParameterSetter;
                         match param {
@@ -245,6 +276,134 @@ impl<'a> ToTokens for ParamGroup<'a> {
                     }
                 }
             });
+
+            // TextProgramParameter specifics
+
+            let text_field = Vec::from_iter(
+                self.sorted_by_param_nb()
+                    .expect("availability checked above")
+                    .map(|p| p.field().to_string()),
+            );
+
+            let field_to_text = self
+                .sorted_by_param_nb()
+                .expect("availability checked above")
+                .map(|p| {
+                    let field = p.field();
+                    let field_str = field.to_string();
+
+                    quote! {
+                        if let Some(value) = self.#field.to_text() {
+                            fields.push((#field_str, value));
+                        }
+                    }
+                });
+
+            let set_field_from_text = self
+                .sorted_by_param_nb()
+                .expect("availability checked above")
+                .map(|p| {
+                    let field = p.field();
+                    let field_str = field.to_string();
+
+                    let mut set_field = quote! {
+                        if let Err(err) = self.#field.set_text(value) {
+                            if err.is_inactive_param() {
+                                // Don't propagate
+                                log::trace!("set_from_text: {}", err);
+                            } else {
+                                return Err(err);
+                            }
+                        }
+                    };
+
+                    if p.is_discriminant() {
+                        let variable_range_field =
+                            self.variable_range_fields().map(|param| param.field());
+
+                        set_field.extend(quote! {
+                            #(
+                                crate::jstation::data::VariableRangeParameter::set_discriminant(
+                                    &mut self.#variable_range_field,
+                                    self.#field.into(),
+                                );
+                            )*
+                        });
+                    }
+
+                    quote! {
+                        if let Some(value) = get(#field_str) {
+                            #set_field
+                        }
+                    }
+                });
+
+            // DiffProgramParameter specifics
+
+            let block = group_name.to_string().to_title_case();
+
+            let diff_field = self
+                .sorted_by_param_nb()
+                .expect("availability checked above")
+                .map(|p| {
+                    let field = p.field();
+                    let display_from = p.display_value(quote! { self.#field });
+                    let display_to = p.display_value(quote! { other.#field });
+
+                    quote! {
+                        // Text values are `None` for inactive params.
+                        let (from, to) = (self.#field.to_text(), other.#field.to_text());
+                        if from != to {
+                            diffs.push(crate::jstation::data::ParameterDiff {
+                                block: #block,
+                                // The name can depend on the other params, e.g. the effect type.
+                                name: self.#field.param_name(),
+                                from: from.map(|_| #display_from),
+                                to: to.map(|_| #display_to),
+                            });
+                        }
+                    }
+                });
+
+            tokens.extend(quote! {
+                impl crate::jstation::data::DiffProgramParameter for #group_name {
+                    fn diff(&self, other: &Self) -> Vec<crate::jstation::data::ParameterDiff> {
+                        #[allow(unused_imports)]
+                        use crate::jstation::data::{BoolParameter, DiscreteParameter, TextParameter};
+
+                        let mut diffs = Vec::new();
+                        #( { #diff_field } )*
+
+                        diffs
+                    }
+                }
+            });
+
+            tokens.extend(quote! {
+                impl crate::jstation::data::TextProgramParameter for #group_name {
+                    const TEXT_FIELDS: &'static [&'static str] = &[#( #text_field, )*];
+
+                    fn to_text(&self) -> Vec<(&'static str, crate::jstation::data::TextValue)> {
+                        use crate::jstation::data::TextParameter;
+
+                        let mut fields = Vec::new();
+                        #( #field_to_text )*
+
+                        fields
+                    }
+
+                    fn set_from_text<'a>(
+                        &mut self,
+                        get: impl Fn(&str) -> Option<&'a crate::jstation::data::TextValue>,
+                    ) -> Result<(), crate::jstation::Error> {
+                        use crate::jstation::data::TextParameter;
+
+                        #( #set_field_from_text )*
+
+                        Ok(())
+                    }
+                }
+            });
         }
 
         // CCParameter specifics
@@ -288,7 +447,21 @@ impl<'a> ToTokens for ParamGroup<'a> {
                 })
             });
 
+            // The discriminant must be sent first so the device
+            // interprets the variable range params accordingly.
+            let mut cc_params = Vec::from_iter(self.params.iter().filter(|p| p.cc_nb().is_some()));
+            cc_params.sort_by_key(|p| !p.is_discriminant());
+            let cc_param_variant = cc_params.iter().map(|p| p.ty());
+            let cc_param_field = cc_params.iter().map(|p| p.field());
+
             quote! {
+                impl #group_name {
+                    /// Returns the params which can be sent as CCs, discriminant first.
+                    pub fn cc_params(&self) -> Vec<Parameter> {
+                        vec![#( Parameter::#cc_param_variant(self.#cc_param_field), )*]
+                    }
+                }
+
                 impl crate::jstation::data::CCParameter for Parameter {
                     fn to_cc(self) -> Option<crate::midi::CC> {
                         use crate::jstation::data::CCParameter;
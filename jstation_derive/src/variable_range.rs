@@ -96,6 +96,10 @@ impl<'a> ToTokens for VariableRange<'a> {
                 impl crate::jstation::data::ParameterSetter for #param {
                     type Parameter = Self;
 
+                    fn get(&self, _param: Self) -> Self {
+                        *self
+                    }
+
                     fn set(&mut self, new: Self) -> Option<Self> {
                         use crate::jstation::data::DiscreteParameter;
 
@@ -172,6 +176,27 @@ impl<'a> ToTokens for VariableRange<'a> {
                         data.buf_mut()[PARAM_NB.as_usize()] = self.value;
                     }
                 }
+
+                impl crate::jstation::data::TextParameter for #param {
+                    fn to_text(self) -> Option<crate::jstation::data::TextValue> {
+                        use crate::jstation::data::VariableRangeParameter;
+
+                        self.range()?;
+
+                        Some(crate::jstation::data::TextValue::Int(self.value.as_u8().into()))
+                    }
+
+                    fn set_text(
+                        &mut self,
+                        value: &crate::jstation::data::TextValue,
+                    ) -> Result<(), crate::jstation::Error> {
+                        use crate::jstation::data::VariableRangeParameter;
+
+                        *self = Self::try_from_raw(self.discr, value.try_to_raw(#param_name)?)?;
+
+                        Ok(())
+                    }
+                }
             });
         }
 
@@ -0,0 +1,376 @@
+//! Headless command line tool to control a J-Station.
+//!
+//! See `jstation-cli help` for the available commands.
+
+use std::{
+    fs,
+    future::Future,
+    io::{self, IsTerminal, Write},
+    path::PathBuf,
+    process::ExitCode,
+    sync::Arc,
+    time::Duration,
+};
+
+use jstation_controller::{
+    jstation::{
+        data::{dsp, CCParameter, TextValue},
+        prelude::*,
+        preset,
+        simulator::Simulator,
+        syx, Error, JStation, Listener, Message, Program, ProgramId, ProgramNb, Requester,
+    },
+    midi::{self, Scannable},
+    APP_NAME,
+};
+
+const USAGE: &str = "\
+Usage: jstation-cli [--simulator] <command>
+
+Commands:
+    scan                      Find the J-Station on the MIDI ports
+    dump-bank [file.syx]      Dump the user Programs to file.syx or stdout
+    load-bank <file.syx>      Store the Programs from file.syx to the device
+    get-program [-f] <1.2>    Print the user (or factory with -f) Program as a text preset
+    set <param> <value>       Set a parameter of the edit buffer, e.g. `set amp.gain 60`
+    tuner <on|off>            Turn the tuner on or off
+    utility-settings          Print the Utility Settings
+    help                      Print this message
+
+Options:
+    --simulator               Use a simulated J-Station instead of the MIDI ports";
+
+/// Max duration to wait for the next device message.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug)]
+enum Command {
+    Scan,
+    DumpBank(Option<PathBuf>),
+    LoadBank(PathBuf),
+    GetProgram(ProgramId),
+    Set { param: String, value: TextValue },
+    Tuner(bool),
+    UtilitySettings,
+}
+
+impl Command {
+    fn parse(args: &[String]) -> Result<Command, String> {
+        let args = Vec::from_iter(args.iter().map(String::as_str));
+
+        match args.as_slice() {
+            ["scan"] => Ok(Command::Scan),
+            ["dump-bank"] => Ok(Command::DumpBank(None)),
+            ["dump-bank", path] => Ok(Command::DumpBank(Some(path.into()))),
+            ["load-bank", path] => Ok(Command::LoadBank(path.into())),
+            ["get-program", nb] => Ok(Command::GetProgram(ProgramId::new_user(parse_nb(nb)?))),
+            ["get-program", "-f", nb] => {
+                Ok(Command::GetProgram(ProgramId::new_factory(parse_nb(nb)?)))
+            }
+            ["set", param, value] => Ok(Command::Set {
+                param: param.to_string(),
+                value: TextValue::from(*value),
+            }),
+            ["tuner", "on"] => Ok(Command::Tuner(true)),
+            ["tuner", "off"] => Ok(Command::Tuner(false)),
+            ["utility-settings"] => Ok(Command::UtilitySettings),
+            [] => Err("Missing command".to_string()),
+            _ => Err(format!("Invalid command `{}`", args.join(" "))),
+        }
+    }
+}
+
+fn parse_nb(nb: &str) -> Result<ProgramNb, String> {
+    nb.parse().map_err(|err: Error| err.to_string())
+}
+
+/// A connected J-Station.
+struct Device {
+    jstation: JStation,
+    listener: Option<Listener>,
+}
+
+impl Device {
+    fn new() -> Self {
+        Device {
+            jstation: JStation::new(APP_NAME.clone()),
+            listener: None,
+        }
+    }
+
+    /// Scans the MIDI ports for a J-Station.
+    fn scan() -> Result<Self, Error> {
+        let mut device = Device::new();
+        device.jstation.refresh()?;
+
+        let mut ctx = midi::scanner::Context::new(&device).connect_next(&mut device);
+        while let Some(cur_ctx) = ctx {
+            match device.next_msg() {
+                Ok(msg) => {
+                    device.handle(msg)?;
+                    return Ok(device);
+                }
+                Err(err) if err.is_handshake_timeout() => (),
+                Err(err) => return Err(err),
+            }
+
+            device.listener = None;
+            ctx = cur_ctx.connect_next(&mut device);
+        }
+
+        Err(Error::DeviceNotFound)
+    }
+
+    /// Connects to an in-process simulated J-Station.
+    fn simulator() -> Result<Self, Error> {
+        let mut device = Device::new();
+
+        let (transport, incoming) = Simulator::new().spawn();
+        let listener = device
+            .jstation
+            .iface_mut()
+            .connect_transport(transport, incoming)?;
+        device.listener = Some(listener);
+
+        let msg = device.next_msg()?;
+        device.handle(msg)?;
+
+        Ok(device)
+    }
+
+    /// Awaits the next device message.
+    fn next_msg(&mut self) -> Result<Message, Error> {
+        let listener = self.listener.as_mut().ok_or(Error::MidiNotConnected)?;
+
+        smol::block_on(smol::future::or(listener.listen(), async {
+            smol::Timer::after(RESPONSE_TIMEOUT).await;
+            Err(Error::ResponseTimeout)
+        }))
+    }
+
+    fn handle(&mut self, msg: Message) -> Result<(), Error> {
+        self.jstation.handle_device(msg)
+    }
+
+    /// Runs the request built by `req` to completion, handling device messages meanwhile.
+    fn run<T, F>(&mut self, req: impl FnOnce(Requester) -> F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        enum Next<T> {
+            Done(Result<T, Error>),
+            Msg(Result<Message, Error>),
+        }
+
+        let req = req(self.jstation.iface().requester()?);
+        let listener = self.listener.as_mut().ok_or(Error::MidiNotConnected)?;
+        let jstation = &mut self.jstation;
+
+        smol::block_on(async {
+            smol::pin!(req);
+            loop {
+                let next = smol::future::or(async { Next::Done(req.as_mut().await) }, async {
+                    Next::Msg(listener.listen().await)
+                })
+                .await;
+
+                match next {
+                    Next::Done(res) => return res,
+                    Next::Msg(msg) => jstation.handle_device(msg?)?,
+                }
+            }
+        })
+    }
+
+    /// Requests the Programs & the edit buffer after the handshake.
+    fn sync(&mut self) -> Result<(), Error> {
+        let unreadable = self.run(Requester::sync_device)?;
+        if !unreadable.is_empty() {
+            let ids = Vec::from_iter(unreadable.iter().map(ToString::to_string));
+            eprintln!("Warning: couldn't read Programs {}", ids.join(", "));
+        }
+
+        Ok(())
+    }
+
+    fn user_programs(&self) -> impl Iterator<Item = &Program> {
+        ProgramNb::enumerate().filter_map(|nb| self.jstation.get_program(ProgramId::new_user(nb)))
+    }
+}
+
+impl Scannable for Device {
+    type In = ();
+    type Out = ();
+    type Error = Error;
+
+    fn ins(&self) -> &midi::PortsIn {
+        &self.jstation.iface().ins
+    }
+
+    fn outs(&self) -> &midi::PortsOut {
+        &self.jstation.iface().outs
+    }
+
+    fn connect(&mut self, port_in: Arc<str>, port_out: Arc<str>) -> Result<((), ()), Error> {
+        let (listener, ()) = self.jstation.iface_mut().connect(port_in, port_out)?;
+        self.listener = Some(listener);
+
+        Ok(((), ()))
+    }
+
+    fn connect_in(&mut self, port_name: Arc<str>) -> Result<(), Error> {
+        self.listener = Some(self.jstation.iface_mut().connect_in(port_name)?);
+
+        Ok(())
+    }
+
+    fn connect_out(&mut self, port_name: Arc<str>) -> Result<(), Error> {
+        self.jstation.iface_mut().connect_out(port_name)
+    }
+}
+
+fn run(cmd: Command, use_simulator: bool) -> Result<(), Box<dyn std::error::Error>> {
+    if matches!(cmd, Command::Scan) && !use_simulator {
+        let mut jstation = JStation::new(APP_NAME.clone());
+        jstation.refresh()?;
+        for port in jstation.iface().ins.list() {
+            eprintln!("In port: {port}");
+        }
+        for port in jstation.iface().outs.list() {
+            eprintln!("Out port: {port}");
+        }
+    }
+
+    let mut device = if use_simulator {
+        Device::simulator()?
+    } else {
+        Device::scan()?
+    };
+
+    match cmd {
+        Command::Scan => {
+            let (port_in, port_out) = device
+                .jstation
+                .iface()
+                .connected_ports()
+                .unwrap_or_else(|| ("simulator".into(), "simulator".into()));
+            println!("Found J-Station on in: {port_in}, out: {port_out}");
+        }
+        Command::DumpBank(path) => {
+            device.sync()?;
+            let buf = syx::build_bank(device.user_programs());
+
+            match path {
+                Some(path) => fs::write(&path, buf)?,
+                None => {
+                    let mut stdout = io::stdout();
+                    if stdout.is_terminal() {
+                        return Err("Redirect the output to a file or provide a path".into());
+                    }
+                    stdout.write_all(&buf)?;
+                }
+            }
+        }
+        Command::LoadBank(path) => {
+            device.sync()?;
+            device.jstation.import_bank(&path)?;
+
+            let batch = device.jstation.store_pending_bank()?;
+            // Fails if the device rejected or didn't acknowledge a Program.
+            let progs = device.run(|requester| requester.store_programs(batch))?;
+            device.jstation.have_stored(progs);
+
+            eprintln!("Bank stored");
+        }
+        Command::GetProgram(prog_id) => {
+            device.sync()?;
+
+            if device.jstation.get_program(prog_id).is_none() {
+                device.run(|requester| async move { requester.request_program(prog_id).await })?;
+            }
+
+            let prog = device
+                .jstation
+                .get_program(prog_id)
+                .ok_or(Error::ProgramUnknown(prog_id))?;
+            print!("{}", preset::serialize(prog.data())?);
+        }
+        Command::Set { param, value } => {
+            device.run(|requester| async move { requester.program_update_req().await })?;
+
+            let mut dsp = device.jstation.dsp().clone();
+            preset::set_param(&mut dsp, &param, &value)?;
+
+            // Only send the CCs which changed, e.g. a single param.
+            let prev = device.jstation.dsp();
+            let changed = Vec::from_iter(
+                dsp::BlockKind::ALL
+                    .into_iter()
+                    .flat_map(|kind| {
+                        prev.block(kind)
+                            .params()
+                            .into_iter()
+                            .zip(dsp.block(kind).params())
+                    })
+                    .filter_map(|(prev, new)| {
+                        new.to_cc()
+                            .filter(|cc| prev.to_cc().map(|prev| prev.value) != Some(cc.value))
+                    }),
+            );
+            for cc in changed {
+                device.jstation.iface_mut().send_cc(cc)?;
+            }
+        }
+        Command::Tuner(true) => device.jstation.tuner_on()?,
+        Command::Tuner(false) => device.jstation.tuner_off()?,
+        Command::UtilitySettings => {
+            device.run(|requester| async move { requester.request_utility_settings().await })?;
+
+            let settings = device.jstation.dsp().utility_settings;
+            println!("stereo_mono = {}", settings.stereo_mono);
+            println!("dry_track = {}", settings.dry_track);
+            println!("digital_out_level = {}", settings.digital_out_level);
+            println!("global_cabinet = {}", settings.global_cabinet);
+            println!("midi_merge = {}", settings.midi_merge);
+            println!("midi_channel = {}", settings.midi_channel);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn main() -> ExitCode {
+    env_logger::Builder::new()
+        .filter_module("jstation_controller", log::LevelFilter::Warn)
+        .parse_default_env()
+        .init();
+
+    let mut args = Vec::from_iter(std::env::args().skip(1));
+
+    let use_simulator = args.first().map(String::as_str) == Some("--simulator");
+    if use_simulator {
+        args.remove(0);
+    }
+
+    if let [help] = args.as_slice() {
+        if help == "help" || help == "--help" || help == "-h" {
+            println!("{USAGE}");
+            return ExitCode::SUCCESS;
+        }
+    }
+
+    let cmd = match Command::parse(&args) {
+        Ok(cmd) => cmd,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            return ExitCode::FAILURE;
+        }
+    };
+
+    if let Err(err) = run(cmd, use_simulator) {
+        eprintln!("Error: {err}");
+        return ExitCode::FAILURE;
+    }
+
+    ExitCode::SUCCESS
+}
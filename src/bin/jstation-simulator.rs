@@ -0,0 +1,34 @@
+//! Exposes a virtual J-Station on MIDI ports.
+//!
+//! Run this, then select the `J-Station Simulator` ports in the application.
+
+#[cfg(unix)]
+pub fn main() {
+    use jstation_controller::jstation::simulator::Simulator;
+
+    env_logger::Builder::new()
+        .filter_module("jstation_controller", log::LevelFilter::Debug)
+        .init();
+
+    let _ports = match Simulator::new().run_virtual() {
+        Ok(ports) => ports,
+        Err(err) => {
+            eprintln!("Failed to run the simulator: {err}");
+            std::process::exit(1);
+        }
+    };
+
+    println!(
+        "{} running, press Ctrl+C to quit",
+        Simulator::VIRTUAL_PORT_NAME
+    );
+    loop {
+        std::thread::park();
+    }
+}
+
+#[cfg(not(unix))]
+pub fn main() {
+    eprintln!("Virtual MIDI ports are not supported on this platform");
+    std::process::exit(1);
+}
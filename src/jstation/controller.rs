@@ -0,0 +1,339 @@
+use std::path::PathBuf;
+
+use toml::{Table, Value};
+
+use crate::{
+    jstation::{
+        data::{dsp, CCParameter, CCParameterSetter},
+        toml_file::{self, as_table},
+        Error,
+    },
+    midi,
+};
+
+const FILE: &str = "controllers.toml";
+const SETUP: &str = "setup";
+const PORT: &str = "port";
+const MAPPING: &str = "mapping";
+const CHANNEL: &str = "channel";
+const CC: &str = "cc";
+const PARAMETER: &str = "parameter";
+
+/// A control of an external MIDI controller, e.g. a knob of a fader box.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Control {
+    pub chan: midi::Channel,
+    pub nb: midi::CCNumber,
+}
+
+/// Maps a `Control` to the J-Station parameter with the CC number `target`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Mapping {
+    pub control: Control,
+    pub target: midi::CCNumber,
+}
+
+impl Mapping {
+    /// Translates the `value` received for the `Control` into the target parameter.
+    ///
+    /// Returns `Ok(None)` if the value of the parameter in `dsp` is unchanged.
+    pub fn translate(
+        &self,
+        dsp: &dsp::Dsp,
+        value: midi::CCValue,
+    ) -> Result<Option<dsp::Parameter>, Error> {
+        // Scales the value to the range of the target parameter.
+        dsp.clone().set_cc(midi::CC::new(self.target, value))
+    }
+
+    /// Returns the target parameter, e.g. for display.
+    pub fn target_param(&self) -> Option<dsp::Parameter> {
+        let dsp = dsp::Dsp::default();
+        dsp::BlockKind::ALL
+            .into_iter()
+            .flat_map(|kind| dsp.block(kind).params())
+            .find(|param| param.to_cc().map(|cc| cc.nb) == Some(self.target))
+    }
+}
+
+/// The `Mapping`s for the controller connected to `port`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Setup {
+    pub port: String,
+    pub mappings: Vec<Mapping>,
+}
+
+impl Setup {
+    pub fn get(&self, control: Control) -> Option<&Mapping> {
+        self.mappings
+            .iter()
+            .find(|mapping| mapping.control == control)
+    }
+
+    /// Maps `control` to `target`, replacing any previous `Mapping` for `control`.
+    pub fn learn(&mut self, control: Control, target: midi::CCNumber) {
+        let mapping = Mapping { control, target };
+        match self
+            .mappings
+            .iter_mut()
+            .find(|mapping| mapping.control == control)
+        {
+            Some(prev) => *prev = mapping,
+            None => self.mappings.push(mapping),
+        }
+    }
+
+    pub fn remove(&mut self, control: Control) {
+        self.mappings.retain(|mapping| mapping.control != control);
+    }
+}
+
+/// The controller `Setup`s, stored in a single text file.
+///
+/// ```toml
+/// [[setup]]
+/// port = "nanoKONTROL2"
+///
+/// [[setup.mapping]]
+/// channel = 1
+/// cc = 20
+/// parameter = 34
+/// ```
+///
+/// `parameter` is the CC number of the J-Station parameter.
+#[derive(Debug)]
+pub struct Setups {
+    path: PathBuf,
+    pub setups: Vec<Setup>,
+}
+
+impl Setups {
+    /// Returns the default `Setups` file for current user.
+    pub fn default_path() -> Option<PathBuf> {
+        toml_file::default_path(FILE)
+    }
+
+    /// Opens the `Setups` at `path`, which is created when saved if needed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+
+        let setups = match toml_file::read(&path)? {
+            Some(input) => parse(&input)?,
+            None => Vec::new(),
+        };
+
+        Ok(Setups { path, setups })
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        toml_file::write(&self.path, serialize(&self.setups))
+    }
+
+    pub fn get(&self, port: &str) -> Option<&Setup> {
+        self.setups.iter().find(|setup| setup.port == port)
+    }
+
+    /// Returns the `Setup` for `port`, adding it if needed.
+    pub fn get_or_insert(&mut self, port: &str) -> &mut Setup {
+        let idx = match self.setups.iter().position(|setup| setup.port == port) {
+            Some(idx) => idx,
+            None => {
+                self.setups.push(Setup {
+                    port: port.to_string(),
+                    mappings: Vec::new(),
+                });
+                self.setups.len() - 1
+            }
+        };
+
+        &mut self.setups[idx]
+    }
+}
+
+/// Serializes the provided `Setup`s as text.
+pub fn serialize(setups: &[Setup]) -> String {
+    let setups = setups
+        .iter()
+        .map(|setup| {
+            let mappings = setup
+                .mappings
+                .iter()
+                .map(|mapping| {
+                    let mut table = Table::new();
+                    table.insert(
+                        CHANNEL.to_string(),
+                        Value::Integer(mapping.control.chan.as_u8() as i64 + 1),
+                    );
+                    table.insert(
+                        CC.to_string(),
+                        Value::Integer(mapping.control.nb.as_u8().into()),
+                    );
+                    table.insert(
+                        PARAMETER.to_string(),
+                        Value::Integer(mapping.target.as_u8().into()),
+                    );
+
+                    Value::Table(table)
+                })
+                .collect();
+
+            let mut table = Table::new();
+            table.insert(PORT.to_string(), Value::String(setup.port.clone()));
+            table.insert(MAPPING.to_string(), Value::Array(mappings));
+
+            Value::Table(table)
+        })
+        .collect();
+
+    let mut table = Table::new();
+    table.insert(SETUP.to_string(), Value::Array(setups));
+
+    table.to_string()
+}
+
+/// Parses the `Setup`s from the provided text.
+pub fn parse(input: &str) -> Result<Vec<Setup>, Error> {
+    let table: Table = input
+        .parse()
+        .map_err(|err| Error::with_context("Parsing controller setups", err))?;
+
+    let mut setups = Vec::new();
+    for (key, value) in table.iter() {
+        match (key.as_str(), value) {
+            (SETUP, Value::Array(values)) => {
+                for value in values {
+                    setups.push(parse_setup(value)?);
+                }
+            }
+            _ => return Err(unknown(key.clone())),
+        }
+    }
+
+    Ok(setups)
+}
+
+fn parse_setup(value: &Value) -> Result<Setup, Error> {
+    let table = as_table(SETUP, value)?;
+
+    let mut setup = Setup::default();
+    for (key, value) in table.iter() {
+        match (key.as_str(), value) {
+            (PORT, Value::String(port)) => setup.port = port.clone(),
+            (MAPPING, Value::Array(values)) => {
+                for value in values {
+                    setup.mappings.push(parse_mapping(value)?);
+                }
+            }
+            _ => return Err(unknown(format!("{SETUP}.{key}"))),
+        }
+    }
+
+    Ok(setup)
+}
+
+fn parse_mapping(value: &Value) -> Result<Mapping, Error> {
+    let table = as_table(MAPPING, value)?;
+
+    let mut chan = None;
+    let mut nb = None;
+    let mut target = None;
+    for (key, value) in table.iter() {
+        match (key.as_str(), value) {
+            (CHANNEL, Value::Integer(value)) if (1..=16).contains(value) => {
+                chan = Some(midi::Channel::from(*value as u8 - 1));
+            }
+            (CC, Value::Integer(value)) => nb = Some(as_cc_nb(CC, *value)?),
+            (PARAMETER, Value::Integer(value)) => target = Some(as_cc_nb(PARAMETER, *value)?),
+            _ => {
+                return Err(Error::TextValueUnexpected {
+                    param: format!("{MAPPING}.{key}"),
+                    value: value.to_string(),
+                })
+            }
+        }
+    }
+
+    let missing = |field: &str| Error::FieldMissing {
+        file: FILE,
+        field: format!("{MAPPING}.{field}"),
+    };
+
+    Ok(Mapping {
+        control: Control {
+            chan: chan.ok_or_else(|| missing(CHANNEL))?,
+            nb: nb.ok_or_else(|| missing(CC))?,
+        },
+        target: target.ok_or_else(|| missing(PARAMETER))?,
+    })
+}
+
+fn unknown(field: String) -> Error {
+    Error::FieldUnknown { file: FILE, field }
+}
+
+fn as_cc_nb(param: &str, value: i64) -> Result<midi::CCNumber, Error> {
+    u8::try_from(value)
+        .ok()
+        .and_then(|value| midi::CCNumber::try_from(value).ok())
+        .ok_or_else(|| Error::TextValueUnexpected {
+            param: format!("{MAPPING}.{param}"),
+            value: value.to_string(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Control, Setup};
+    use crate::{
+        jstation::{
+            data::{dsp, CCParameter, ConstRangeParameter},
+            Error,
+        },
+        midi,
+    };
+
+    fn control(nb: u8) -> Control {
+        Control {
+            chan: midi::Channel::from(1),
+            nb: midi::CCNumber::new(nb),
+        }
+    }
+
+    #[test]
+    fn learn_and_translate() {
+        let gain = dsp::Parameter::from(dsp::amp::Parameter::from(
+            dsp::amp::Gain::try_from_raw(0.into()).unwrap(),
+        ));
+        let gain_cc = gain.to_cc().unwrap().nb;
+
+        let mut setup = Setup {
+            port: "Faders".to_string(),
+            mappings: Vec::new(),
+        };
+        setup.learn(control(20), midi::CCNumber::new(0));
+        setup.learn(control(20), gain_cc);
+        setup.learn(control(21), gain_cc);
+        assert_eq!(setup.mappings.len(), 2);
+
+        let mapping = setup.get(control(20)).unwrap();
+        assert_eq!(mapping.target_param().unwrap().names(), ("Amp", "Gain"));
+
+        let param = mapping
+            .translate(&dsp::Dsp::default(), midi::CCValue::MAX)
+            .unwrap()
+            .unwrap();
+        assert_eq!(param.to_cc().unwrap().nb, gain_cc);
+        assert_eq!(param.to_cc().unwrap().value, midi::CCValue::MAX);
+
+        setup.remove(control(21));
+        let text = super::serialize(&[setup.clone()]);
+        assert!(text.contains("channel = 2"));
+        assert_eq!(super::parse(&text).unwrap(), vec![setup]);
+
+        assert!(super::parse("[[setup]]\n[[setup.mapping]]\ncc = 200").is_err());
+        assert!(matches!(
+            super::parse("[[setup]]\n[[setup.mapping]]\ncc = 20"),
+            Err(Error::FieldMissing { .. }),
+        ));
+    }
+}
@@ -0,0 +1,92 @@
+use std::fmt;
+
+use crate::jstation::{
+    data::{dsp::Dsp, ProgramData, ProgramParameter},
+    Error,
+};
+
+/// A parameter which differs between two `Program`s.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ParameterDiff {
+    /// The DSP block the parameter belongs to, e.g. `Amp`.
+    pub block: &'static str,
+    /// The parameter name, e.g. `Gain`.
+    pub name: &'static str,
+    /// The value in the original `Program`, `None` if the parameter is inactive.
+    pub from: Option<String>,
+    /// The value in the other `Program`, `None` if the parameter is inactive.
+    pub to: Option<String>,
+}
+
+impl fmt::Display for ParameterDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {} -> {}",
+            self.block,
+            self.name,
+            self.from.as_deref().unwrap_or("-"),
+            self.to.as_deref().unwrap_or("-"),
+        )
+    }
+}
+
+/// A group of parameters which can be compared one by one, e.g. a DSP block.
+///
+/// See also [`ProgramParameter::has_changed`].
+pub trait DiffProgramParameter {
+    /// Returns the parameters which differ from `self` in `other`.
+    fn diff(&self, other: &Self) -> Vec<ParameterDiff>;
+}
+
+/// Returns the parameters which differ from `from` in `to`.
+pub fn diff(from: &ProgramData, to: &ProgramData) -> Result<Vec<ParameterDiff>, Error> {
+    let mut to_dsp = Dsp::default();
+    to_dsp.set_from(to)?;
+
+    diff_dsp(from, &to_dsp)
+}
+
+/// Returns the parameters which differ from `from` in `dsp`, e.g. the edit buffer.
+pub fn diff_dsp(from: &ProgramData, dsp: &Dsp) -> Result<Vec<ParameterDiff>, Error> {
+    let mut from_dsp = Dsp::default();
+    from_dsp.set_from(from)?;
+
+    Ok(from_dsp.diff(dsp))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jstation::{data::dsp::Dsp, ProgramData};
+
+    #[test]
+    fn diff() {
+        let from = ProgramData::default();
+
+        let mut to = from.clone();
+        to.store_name("Crunch");
+        // amp.modeling: Brit Stack
+        to.buf_mut()[9] = 5.into();
+        // reverb.switch
+        let reverb_switch = to.buf()[32].as_u8();
+        to.buf_mut()[32] = (1 - reverb_switch).into();
+        // effect.typ: Pitch / Detune, no regen
+        to.buf_mut()[20] = 6.into();
+
+        let diffs = super::diff(&from, &to).unwrap();
+        let diffs = Vec::from_iter(diffs.iter().map(ToString::to_string));
+        assert_eq!(
+            diffs,
+            [
+                "Program Name:  -> Crunch",
+                "Amp Modeling: J Crunch -> Brit Stack",
+                "Effect Type: Chorus -> Pitch / Detune",
+                "Effect Regen: 0 -> -",
+                "Reverb Switch: Off -> On",
+            ],
+        );
+
+        assert!(super::diff(&to, &to).unwrap().is_empty());
+        assert!(super::diff_dsp(&from, &Dsp::default()).unwrap().is_empty());
+    }
+}
@@ -5,7 +5,14 @@ use jstation_derive::ParameterSetter;
 
 #[derive(Clone, Copy, Debug, Default, ParameterSetter)]
 pub struct Amp {
-    #[const_range(max = 24, param_nb = 9, cc_nb = 34, display_map = name, display_map = nick)]
+    #[const_range(
+        max = 24,
+        param_nb = 9,
+        cc_nb = 34,
+        display_map = name,
+        display_map = nick,
+        text_map = nick
+    )]
     pub modeling: Modeling,
     #[const_range(max = 90, param_nb = 10, cc_nb = 35, display_cents)]
     pub gain: Gain,
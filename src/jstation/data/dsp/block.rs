@@ -0,0 +1,130 @@
+use std::fmt;
+
+use crate::jstation::{
+    data::{ProgramData, ProgramParameter},
+    Error,
+};
+
+use super::{
+    Amp, Cabinet, Compressor, Delay, Dsp, Effect, Expression, NoiseGate, Parameter, Reverb, Wah,
+};
+
+/// The kind of a DSP block which can be copied from a `Program` to another.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum BlockKind {
+    Compressor,
+    Wah,
+    #[default]
+    Amp,
+    Cabinet,
+    NoiseGate,
+    Effect,
+    Delay,
+    Reverb,
+    Expression,
+}
+
+impl BlockKind {
+    pub const ALL: [BlockKind; 9] = [
+        BlockKind::Compressor,
+        BlockKind::Wah,
+        BlockKind::Amp,
+        BlockKind::Cabinet,
+        BlockKind::NoiseGate,
+        BlockKind::Effect,
+        BlockKind::Delay,
+        BlockKind::Reverb,
+        BlockKind::Expression,
+    ];
+}
+
+impl fmt::Display for BlockKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use BlockKind::*;
+        f.write_str(match self {
+            Compressor => "Compressor",
+            Wah => "Wah",
+            Amp => "Amp",
+            Cabinet => "Cabinet",
+            NoiseGate => "Noise Gate",
+            Effect => "Effect",
+            Delay => "Delay",
+            Reverb => "Reverb",
+            Expression => "Expression",
+        })
+    }
+}
+
+/// The parameters of a DSP block, e.g. copied from a `Program`.
+#[derive(Clone, Copy, Debug)]
+pub enum Block {
+    Compressor(Compressor),
+    Wah(Wah),
+    Amp(Amp),
+    Cabinet(Cabinet),
+    NoiseGate(NoiseGate),
+    Effect(Effect),
+    Delay(Delay),
+    Reverb(Reverb),
+    Expression(Expression),
+}
+
+impl Block {
+    /// Builds the `kind` `Block` from the provided `Program` data.
+    pub fn from_data(kind: BlockKind, data: &ProgramData) -> Result<Self, Error> {
+        let mut dsp = Dsp::default();
+        dsp.set_from(data)?;
+
+        Ok(dsp.block(kind))
+    }
+
+    pub fn kind(&self) -> BlockKind {
+        match self {
+            Block::Compressor(_) => BlockKind::Compressor,
+            Block::Wah(_) => BlockKind::Wah,
+            Block::Amp(_) => BlockKind::Amp,
+            Block::Cabinet(_) => BlockKind::Cabinet,
+            Block::NoiseGate(_) => BlockKind::NoiseGate,
+            Block::Effect(_) => BlockKind::Effect,
+            Block::Delay(_) => BlockKind::Delay,
+            Block::Reverb(_) => BlockKind::Reverb,
+            Block::Expression(_) => BlockKind::Expression,
+        }
+    }
+
+    /// Returns the parameters of the `Block` in the order they must be sent to the device.
+    pub fn params(&self) -> Vec<Parameter> {
+        fn into_dsp<P: Into<Parameter>>(params: Vec<P>) -> Vec<Parameter> {
+            Vec::from_iter(params.into_iter().map(Into::into))
+        }
+
+        match self {
+            Block::Compressor(block) => into_dsp(block.cc_params()),
+            Block::Wah(block) => into_dsp(block.cc_params()),
+            Block::Amp(block) => into_dsp(block.cc_params()),
+            Block::Cabinet(block) => into_dsp(block.cc_params()),
+            Block::NoiseGate(block) => into_dsp(block.cc_params()),
+            Block::Effect(block) => into_dsp(block.cc_params()),
+            Block::Delay(block) => into_dsp(block.cc_params()),
+            Block::Reverb(block) => into_dsp(block.cc_params()),
+            Block::Expression(block) => into_dsp(block.cc_params()),
+        }
+    }
+}
+
+impl Dsp {
+    /// Returns a copy of the `kind` `Block`.
+    pub fn block(&self, kind: BlockKind) -> Block {
+        match kind {
+            BlockKind::Compressor => Block::Compressor(self.compressor),
+            BlockKind::Wah => Block::Wah(self.wah),
+            BlockKind::Amp => Block::Amp(self.amp),
+            BlockKind::Cabinet => Block::Cabinet(self.cabinet),
+            BlockKind::NoiseGate => Block::NoiseGate(self.noise_gate),
+            BlockKind::Effect => Block::Effect(self.effect),
+            BlockKind::Delay => Block::Delay(self.delay),
+            BlockKind::Reverb => Block::Reverb(self.reverb),
+            BlockKind::Expression => Block::Expression(self.expression),
+        }
+    }
+}
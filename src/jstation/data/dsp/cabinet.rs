@@ -2,7 +2,14 @@ use jstation_derive::ParameterSetter;
 
 #[derive(Clone, Copy, Debug, Default, ParameterSetter)]
 pub struct Cabinet {
-    #[const_range(max = 18, param_nb = 15, cc_nb = 66, display_map = name, display_map = nick)]
+    #[const_range(
+        max = 18,
+        param_nb = 15,
+        cc_nb = 66,
+        display_map = name,
+        display_map = nick,
+        text_map = nick
+    )]
     pub typ: Type,
 }
 
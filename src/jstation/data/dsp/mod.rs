@@ -1,6 +1,9 @@
 use crate::{
     jstation::{
-        data::{CCParameter, CCParameterSetter, ParameterSetter, ProgramData, ProgramParameter},
+        data::{
+            CCParameter, CCParameterSetter, DiffProgramParameter, ParameterDiff, ParameterSetter,
+            ProgramData, ProgramParameter,
+        },
         Error,
     },
     midi,
@@ -9,6 +12,9 @@ use crate::{
 pub mod amp;
 pub use amp::Amp;
 
+pub mod block;
+pub use block::{Block, BlockKind};
+
 pub mod cabinet;
 pub use cabinet::Cabinet;
 
@@ -39,7 +45,7 @@ pub use utility_settings::UtilitySettings;
 pub mod wah;
 pub use wah::Wah;
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct Dsp {
     pub compressor: Compressor,
     pub wah: Wah,
@@ -101,6 +107,32 @@ impl ProgramParameter for Dsp {
     }
 }
 
+impl DiffProgramParameter for Dsp {
+    fn diff(&self, other: &Self) -> Vec<ParameterDiff> {
+        let mut diffs = Vec::new();
+        if self.name != other.name {
+            diffs.push(ParameterDiff {
+                block: "Program",
+                name: "Name",
+                from: Some(self.name.clone()),
+                to: Some(other.name.clone()),
+            });
+        }
+
+        diffs.extend(self.compressor.diff(&other.compressor));
+        diffs.extend(self.wah.diff(&other.wah));
+        diffs.extend(self.amp.diff(&other.amp));
+        diffs.extend(self.cabinet.diff(&other.cabinet));
+        diffs.extend(self.noise_gate.diff(&other.noise_gate));
+        diffs.extend(self.effect.diff(&other.effect));
+        diffs.extend(self.delay.diff(&other.delay));
+        diffs.extend(self.reverb.diff(&other.reverb));
+        diffs.extend(self.expression.diff(&other.expression));
+
+        diffs
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum Parameter {
     Amp(amp::Parameter),
@@ -116,9 +148,46 @@ pub enum Parameter {
     Wah(wah::Parameter),
 }
 
+impl Parameter {
+    /// Returns the block & the name of the param for display.
+    pub fn names(self) -> (&'static str, &'static str) {
+        use Parameter::*;
+        match self {
+            Amp(param) => param.names(),
+            Cabinet(param) => param.names(),
+            Compressor(param) => param.names(),
+            Delay(param) => param.names(),
+            Effect(param) => param.names(),
+            Expression(param) => param.names(),
+            NoiseGate(param) => param.names(),
+            Pedal(param) => param.names(),
+            Reverb(param) => param.names(),
+            UtilitySettings(param) => param.names(),
+            Wah(param) => param.names(),
+        }
+    }
+}
+
 impl ParameterSetter for Dsp {
     type Parameter = Parameter;
 
+    fn get(&self, param: Parameter) -> Parameter {
+        use Parameter::*;
+        match param {
+            Amp(param) => self.amp.get(param).into(),
+            Cabinet(param) => self.cabinet.get(param).into(),
+            Compressor(param) => self.compressor.get(param).into(),
+            Delay(param) => self.delay.get(param).into(),
+            Expression(param) => self.expression.get(param).into(),
+            Effect(param) => self.effect.get(param).into(),
+            NoiseGate(param) => self.noise_gate.get(param).into(),
+            Reverb(param) => self.reverb.get(param).into(),
+            Wah(param) => self.wah.get(param).into(),
+            Pedal(param) => self.pedal.get(param).into(),
+            UtilitySettings(param) => self.utility_settings.get(param).into(),
+        }
+    }
+
     fn set(&mut self, new: Parameter) -> Option<Parameter> {
         use Parameter::*;
         match new {
@@ -2,10 +2,13 @@
 pub mod parameter;
 pub use parameter::{
     BoolParameter, CCParameter, CCParameterSetter, ConstRangeParameter, DiscreteParameter,
-    DiscreteRange, Normal, ParameterNumber, ParameterSetter, RawValue, VariableRange,
-    VariableRangeParameter,
+    DiscreteRange, Normal, ParameterNumber, ParameterSetter, RawValue, TextParameter,
+    TextProgramParameter, TextValue, VariableRange, VariableRangeParameter,
 };
 
+pub mod diff;
+pub use diff::{DiffProgramParameter, ParameterDiff};
+
 pub mod dsp;
 
 pub mod program;
@@ -13,6 +13,9 @@ pub use normal::Normal;
 mod raw;
 pub use raw::RawValue;
 
+mod text;
+pub use text::{TextParameter, TextProgramParameter, TextValue};
+
 mod variable_range;
 pub use variable_range::{VariableRange, VariableRangeParameter};
 
@@ -69,6 +72,11 @@ impl fmt::Display for ParameterNumber {
 pub trait ParameterSetter {
     type Parameter: Clone + Copy;
 
+    /// Returns the current value of the parameter `param` refers to.
+    ///
+    /// The value of `param` itself is ignored.
+    fn get(&self, param: Self::Parameter) -> Self::Parameter;
+
     /// Sets `Self` with the provided value.
     ///
     /// Returns the updated parameter if its value has changed.
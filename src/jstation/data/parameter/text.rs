@@ -0,0 +1,112 @@
+use std::fmt;
+
+use crate::jstation::{data::RawValue, Error};
+
+/// A parameter value as represented in text presets.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TextValue {
+    Bool(bool),
+    Int(i64),
+    Str(String),
+}
+
+impl TextValue {
+    /// Returns the `RawValue` for this `TextValue` if it is a `u8` integer.
+    ///
+    /// Note: range is not checked at this stage.
+    pub fn try_to_raw(&self, param: &str) -> Result<RawValue, Error> {
+        match self {
+            TextValue::Int(value) => u8::try_from(*value)
+                .map(RawValue::new)
+                .map_err(|_| self.unexpected(param)),
+            _ => Err(self.unexpected(param)),
+        }
+    }
+
+    pub fn unexpected(&self, param: &str) -> Error {
+        Error::TextValueUnexpected {
+            param: param.to_string(),
+            value: self.to_string(),
+        }
+    }
+}
+
+/// Parses a `TextValue` from a command line argument.
+///
+/// `true` & `false` are `Bool`s, integers are `Int`s, anything else is a `Str`.
+impl From<&str> for TextValue {
+    fn from(value: &str) -> Self {
+        match value {
+            "true" => TextValue::Bool(true),
+            "false" => TextValue::Bool(false),
+            _ => value
+                .parse()
+                .map_or_else(|_| TextValue::Str(value.to_string()), TextValue::Int),
+        }
+    }
+}
+
+impl fmt::Display for TextValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TextValue::Bool(value) => fmt::Display::fmt(value, f),
+            TextValue::Int(value) => fmt::Display::fmt(value, f),
+            TextValue::Str(value) => fmt::Debug::fmt(value, f),
+        }
+    }
+}
+
+/// A `ProgramParameter` which can be represented as a `TextValue`.
+pub trait TextParameter: Sized {
+    /// Returns the `TextValue` for `Self` or `None` if the parameter is inactive.
+    fn to_text(self) -> Option<TextValue>;
+
+    /// Sets `Self` from the provided `TextValue`, checking it is in range.
+    fn set_text(&mut self, value: &TextValue) -> Result<(), Error>;
+}
+
+/// A group of `TextParameter`s, e.g. a DSP block.
+pub trait TextProgramParameter {
+    /// The field name of each `TextParameter` in the group.
+    const TEXT_FIELDS: &'static [&'static str];
+
+    /// Returns the field name and `TextValue` of each active parameter.
+    fn to_text(&self) -> Vec<(&'static str, TextValue)>;
+
+    /// Sets the parameters from the `TextValue`s returned by `get` for each field.
+    ///
+    /// Parameters for which `get` returns `None` are left unchanged.
+    fn set_from_text<'a>(
+        &mut self,
+        get: impl Fn(&str) -> Option<&'a TextValue>,
+    ) -> Result<(), Error>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TextValue;
+    use crate::jstation::data::RawValue;
+
+    #[test]
+    fn try_to_raw() {
+        assert_eq!(
+            TextValue::Int(42).try_to_raw("test").unwrap(),
+            RawValue::new(42)
+        );
+        assert!(TextValue::Int(256).try_to_raw("test").is_err());
+        assert!(TextValue::Int(-1).try_to_raw("test").is_err());
+        assert!(TextValue::Bool(true).try_to_raw("test").is_err());
+        assert!(TextValue::Str("42".to_string()).try_to_raw("test").is_err());
+    }
+
+    #[test]
+    fn from_str() {
+        assert_eq!(TextValue::from("60"), TextValue::Int(60));
+        assert_eq!(TextValue::from("-1"), TextValue::Int(-1));
+        assert_eq!(TextValue::from("true"), TextValue::Bool(true));
+        assert_eq!(
+            TextValue::from("Brit Stack"),
+            TextValue::Str("Brit Stack".to_string())
+        );
+    }
+}
@@ -3,12 +3,12 @@ use std::fmt;
 use nom::IResult;
 
 use crate::jstation::{
-    data::{ParameterNumber, RawValue},
+    data::{dsp, ParameterNumber, RawValue},
     take_split_bytes_u8, Error,
 };
 use crate::midi;
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Program {
     id: ProgramId,
     data: ProgramData,
@@ -36,7 +36,7 @@ impl Program {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ProgramData {
     buf: Box<[RawValue; Self::PARAM_COUNT]>,
     name: String,
@@ -94,6 +94,20 @@ impl ProgramData {
     }
 }
 
+impl Default for ProgramData {
+    /// Builds a `ProgramData` with the values of a default `Dsp`.
+    fn default() -> Self {
+        let mut data = ProgramData {
+            buf: [RawValue::ZERO; Self::PARAM_COUNT].into(),
+            name: String::new(),
+        };
+
+        dsp::Dsp::default().store(&mut data);
+
+        data
+    }
+}
+
 impl ProgramData {
     pub fn parse<'i>(
         input: &'i [u8],
@@ -177,6 +191,16 @@ impl ProgramNb {
     pub fn enumerate() -> ProgramNbIter {
         ProgramNbIter { cur: 0 }
     }
+
+    /// Returns whether `self` is ahead of `prev` when stepping through the Programs.
+    ///
+    /// The Programs wrap around, e.g. `0.1` is ahead of `9.3`.
+    pub fn is_ahead_of(self, prev: ProgramNb) -> bool {
+        let count = Self::PRESET_BANKS * Self::PRESETS;
+        let forward = (self.0 + count - prev.0) % count;
+
+        forward != 0 && forward <= count / 2
+    }
 }
 
 impl TryFrom<u8> for ProgramNb {
@@ -211,6 +235,24 @@ impl fmt::Display for ProgramNb {
     }
 }
 
+/// Parses a `ProgramNb` as displayed, e.g. `1.2`.
+impl std::str::FromStr for ProgramNb {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || Error::ProgramNumberInvalid(s.to_string());
+
+        let (preset_bank, preset) = s.split_once('.').ok_or_else(invalid)?;
+        let preset_bank: u8 = preset_bank.parse().map_err(|_| invalid())?;
+        let preset: u8 = preset.parse().map_err(|_| invalid())?;
+        if preset_bank >= Self::PRESET_BANKS || !(1..=Self::PRESETS).contains(&preset) {
+            return Err(invalid());
+        }
+
+        ProgramNb::try_from(preset_bank * Self::PRESETS + preset - 1)
+    }
+}
+
 pub struct ProgramNbIter {
     cur: u8,
 }
@@ -350,6 +392,13 @@ impl ProgramId {
     pub fn nb(self) -> ProgramNb {
         self.nb
     }
+
+    /// Iterates over the user then the factory `ProgramId`s.
+    pub fn enumerate() -> impl Iterator<Item = ProgramId> {
+        [ProgramsBank::User, ProgramsBank::Factory]
+            .into_iter()
+            .flat_map(|bank| ProgramNb::enumerate().map(move |nb| ProgramId::new(bank, nb)))
+    }
 }
 
 impl From<midi::ProgramNumber> for ProgramId {
@@ -373,3 +422,33 @@ impl fmt::Display for ProgramId {
         f.write_fmt(format_args!("({})", self.bank))
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ProgramNb;
+
+    #[test]
+    fn program_nb_from_str() {
+        for nb in ProgramNb::enumerate() {
+            assert_eq!(nb.to_string().parse::<ProgramNb>().unwrap(), nb);
+        }
+
+        assert_eq!(u8::from("1.2".parse::<ProgramNb>().unwrap()), 4);
+        assert!("1".parse::<ProgramNb>().is_err());
+        assert!("1.0".parse::<ProgramNb>().is_err());
+        assert!("1.4".parse::<ProgramNb>().is_err());
+        assert!("10.1".parse::<ProgramNb>().is_err());
+        assert!("a.1".parse::<ProgramNb>().is_err());
+    }
+
+    #[test]
+    fn program_nb_is_ahead_of() {
+        let nb = |s: &str| s.parse::<ProgramNb>().unwrap();
+
+        assert!(nb("1.3").is_ahead_of(nb("1.2")));
+        assert!(!nb("1.2").is_ahead_of(nb("1.3")));
+        assert!(!nb("1.2").is_ahead_of(nb("1.2")));
+        assert!(nb("0.1").is_ahead_of(nb("9.3")));
+        assert!(!nb("9.3").is_ahead_of(nb("0.1")));
+    }
+}
@@ -1,6 +1,9 @@
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 
-use crate::midi;
+use crate::{
+    jstation::{procedure, Operation, ProgramId},
+    midi,
+};
 
 #[derive(Clone, Debug, thiserror::Error)]
 pub enum Error {
@@ -20,9 +23,27 @@ pub enum Error {
     #[error("Value {} out of range: ({}..={})", .value, .min, .max)]
     ValueOutOfRange { value: u8, min: u8, max: u8 },
 
+    #[error("Unexpected value {} for {}", .value, .param)]
+    TextValueUnexpected { param: String, value: String },
+
+    #[error("Unknown preset field {}", .0)]
+    PresetFieldUnknown(String),
+
+    #[error("Library entry {} not found", .0.display())]
+    LibraryEntryNotFound(PathBuf),
+
+    #[error("Unknown field {} in {}", .field, .file)]
+    FieldUnknown { file: &'static str, field: String },
+
+    #[error("Missing field {} in {}", .field, .file)]
+    FieldMissing { file: &'static str, field: String },
+
     #[error("Program number {} out of range", .0)]
     ProgramNumberOutOfRange(u8),
 
+    #[error("Invalid Program number {}", .0)]
+    ProgramNumberInvalid(String),
+
     #[error("Program data size {} out of range", .0)]
     ProgramDataOutOfRange(usize),
 
@@ -32,8 +53,17 @@ pub enum Error {
     #[error("Unknown Programs Bank {}", .0)]
     ProgramsBank(u8),
 
-    #[error("Failed to identify Program update")]
-    ProgramIdenticationFailure,
+    #[error("No Programs found")]
+    ProgramsNotFound,
+
+    #[error("Program {} not received", .0)]
+    ProgramUnknown(ProgramId),
+
+    #[error("{} failed: {}", .op, .source)]
+    DeviceRequest {
+        op: Operation,
+        source: procedure::result::Error,
+    },
 
     #[error("Error Parsing MIDI message")]
     Parse,
@@ -50,12 +80,24 @@ pub enum Error {
     #[error("An error occured sending a MIDI message")]
     MidiSend,
 
+    #[error("Controller subscription closed")]
+    ControllerSubscriptionClosed,
+
     #[error("Normal out of range: {}", .0)]
     NormalOutOfRange(f32),
 
     #[error("Device handshake timed out")]
     HandshakeTimeout,
 
+    #[error("J-Station not found")]
+    DeviceNotFound,
+
+    #[error("Device response timed out")]
+    ResponseTimeout,
+
+    #[error("{} timed out", .0)]
+    RequestTimeout(Operation),
+
     #[error("{}: {}", ctx, source)]
     WithContext {
         ctx: Arc<str>,
@@ -79,6 +121,16 @@ impl Error {
         matches!(self, Error::HandshakeTimeout)
     }
 
+    /// Returns the `Operation` to retry if this is a retryable device error.
+    pub fn retry_op(&self) -> Option<Operation> {
+        match self {
+            Error::DeviceRequest { op, .. } | Error::RequestTimeout(op) if op.can_retry() => {
+                Some(*op)
+            }
+            _ => None,
+        }
+    }
+
     pub fn is_unknown_cc(&self) -> bool {
         matches!(self, Error::CCNumberUnknown(_))
     }
@@ -0,0 +1,211 @@
+use std::time::{Duration, Instant};
+
+use crate::jstation::{data::CCParameter, dsp};
+
+/// An edit of the edit buffer which can be undone.
+#[derive(Clone, Debug)]
+pub enum Edit {
+    Parameter {
+        prev: dsp::Parameter,
+        new: dsp::Parameter,
+    },
+    Rename {
+        prev: String,
+        new: String,
+    },
+    Block {
+        prev: dsp::Block,
+        new: dsp::Block,
+    },
+}
+
+impl Edit {
+    /// Returns the `Edit` which reverts `self`.
+    pub fn reverse(&self) -> Edit {
+        match self {
+            Edit::Parameter { prev, new } => Edit::Parameter {
+                prev: *new,
+                new: *prev,
+            },
+            Edit::Rename { prev, new } => Edit::Rename {
+                prev: new.clone(),
+                new: prev.clone(),
+            },
+            Edit::Block { prev, new } => Edit::Block {
+                prev: *new,
+                new: *prev,
+            },
+        }
+    }
+
+    /// Tries to merge `other`, an `Edit` which occurred right after `self`.
+    ///
+    /// Returns `Err(other)` if the `Edit`s don't apply to the same parameter.
+    fn merge(&mut self, other: Edit) -> Result<(), Edit> {
+        match (self, other) {
+            (Edit::Parameter { new, .. }, Edit::Parameter { new: other, .. })
+                if is_same_param(*new, other) =>
+            {
+                *new = other;
+            }
+            (Edit::Rename { new, .. }, Edit::Rename { new: other, .. }) => *new = other,
+            (_, other) => return Err(other),
+        }
+
+        Ok(())
+    }
+}
+
+fn is_same_param(param: dsp::Parameter, other: dsp::Parameter) -> bool {
+    match (param.to_cc(), other.to_cc()) {
+        (Some(cc), Some(other)) => cc.nb == other.nb,
+        _ => false,
+    }
+}
+
+/// An undo / redo history of the edit buffer.
+///
+/// Successive `Edit`s of the same parameter are merged when they
+/// occur in a row, e.g. while a slider is dragged.
+#[derive(Debug, Default)]
+pub struct History {
+    undo: Vec<Edit>,
+    redo: Vec<Edit>,
+    /// When the last `Edit` was pushed, if it can be merged.
+    last_push: Option<Instant>,
+}
+
+impl History {
+    /// Max number of `Edit`s which can be undone.
+    const MAX_LEN: usize = 200;
+    /// Max delay between two `Edit`s of the same parameter for them to be merged.
+    const MERGE_DELAY: Duration = Duration::from_millis(800);
+
+    /// Pushes a new `Edit`, discarding the `Edit`s which were undone.
+    pub fn push(&mut self, edit: Edit) {
+        let now = Instant::now();
+        self.redo.clear();
+
+        let is_recent =
+            matches!(self.last_push, Some(at) if now.duration_since(at) < Self::MERGE_DELAY);
+        self.last_push = Some(now);
+
+        let edit = match self.undo.last_mut() {
+            Some(last) if is_recent => match last.merge(edit) {
+                Ok(()) => return,
+                Err(edit) => edit,
+            },
+            _ => edit,
+        };
+
+        if self.undo.len() == Self::MAX_LEN {
+            self.undo.remove(0);
+        }
+
+        self.undo.push(edit);
+    }
+
+    /// Pops the last `Edit` and returns the `Edit` which reverts it.
+    pub fn undo(&mut self) -> Option<Edit> {
+        let edit = self.undo.pop()?;
+        self.last_push = None;
+        let reverse = edit.reverse();
+        self.redo.push(edit);
+
+        Some(reverse)
+    }
+
+    /// Returns the last undone `Edit` so it can be applied again.
+    pub fn redo(&mut self) -> Option<Edit> {
+        let edit = self.redo.pop()?;
+        self.last_push = None;
+        self.undo.push(edit.clone());
+
+        Some(edit)
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+        self.last_push = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Edit, History};
+    use crate::jstation::{data::ConstRangeParameter, dsp};
+
+    fn gain(value: u8) -> dsp::Parameter {
+        dsp::amp::Parameter::from(dsp::amp::Gain::try_from_raw(value.into()).unwrap()).into()
+    }
+
+    fn bass(value: u8) -> dsp::Parameter {
+        dsp::amp::Parameter::from(dsp::amp::Bass::try_from_raw(value.into()).unwrap()).into()
+    }
+
+    fn rename(prev: &str, new: &str) -> Edit {
+        Edit::Rename {
+            prev: prev.to_string(),
+            new: new.to_string(),
+        }
+    }
+
+    #[test]
+    fn undo_redo() {
+        let mut history = History::default();
+        assert!(history.undo().is_none());
+
+        // Merged with the next one
+        history.push(Edit::Parameter {
+            prev: gain(10),
+            new: gain(20),
+        });
+        history.push(Edit::Parameter {
+            prev: gain(20),
+            new: gain(30),
+        });
+        history.push(Edit::Parameter {
+            prev: bass(10),
+            new: bass(40),
+        });
+        history.push(rename("Clean", "Clean 2"));
+
+        let Some(Edit::Rename { new, .. }) = history.undo() else {
+            panic!("Expected a rename");
+        };
+        assert_eq!(new, "Clean");
+
+        let Some(Edit::Parameter { new, .. }) = history.undo() else {
+            panic!("Expected a parameter");
+        };
+        assert_eq!(format!("{new:?}"), format!("{:?}", bass(10)));
+
+        let Some(Edit::Parameter { new, .. }) = history.undo() else {
+            panic!("Expected a parameter");
+        };
+        assert_eq!(format!("{new:?}"), format!("{:?}", gain(10)));
+        assert!(!history.can_undo());
+
+        let Some(Edit::Parameter { new, .. }) = history.redo() else {
+            panic!("Expected a parameter");
+        };
+        assert_eq!(format!("{new:?}"), format!("{:?}", gain(30)));
+        assert!(history.can_redo());
+
+        // A new edit discards the undone ones.
+        history.push(rename("Clean", "Crunch"));
+        assert!(!history.can_redo());
+        assert!(matches!(history.undo(), Some(Edit::Rename { .. })));
+        assert!(matches!(history.undo(), Some(Edit::Parameter { .. })));
+        assert!(history.undo().is_none());
+    }
+}
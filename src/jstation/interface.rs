@@ -3,23 +3,31 @@ use std::sync::Arc;
 
 use crate::{
     jstation::{
-        self, dsp, parse_raw_midi_msg, procedure, sysex, Error, Message, Procedure,
-        ProcedureBuilder, Program,
+        dsp, parse_raw_midi_msg, procedure,
+        request::{PendingRequest, SharedTransport},
+        sysex,
+        transport::{Incoming, Midir, Transport},
+        Error, Message, Procedure, ProcedureBuilder, ProgramData, RequestConfig, Requester,
     },
     midi,
 };
 
-use std::time::Duration;
+use std::{
+    sync::Mutex,
+    time::{Duration, Instant},
+};
 
 const HANDSHAKE_TIMEOUT: Duration = Duration::from_millis(200);
 
 pub struct Interface {
     pub ins: midi::PortsIn,
     pub outs: midi::PortsOut,
-    midi_out: Option<midir::MidiOutputConnection>,
+    midi_out: Option<SharedTransport>,
     cc_chan: midi::Channel,
     sysex_chan: midi::Channel,
     chan_tx: Option<mpsc::Sender<midi::Channel>>,
+    req_tx: Option<mpsc::UnboundedSender<PendingRequest>>,
+    request_config: RequestConfig,
 }
 
 /// General Interface behaviour.
@@ -36,6 +44,8 @@ impl Interface {
             cc_chan: midi::Channel::ALL,
             sysex_chan: midi::Channel::ALL,
             chan_tx: None,
+            req_tx: None,
+            request_config: RequestConfig::default(),
         }
     }
 
@@ -54,12 +64,33 @@ impl Interface {
         self.ins.disconnect();
 
         self.outs.disconnect();
-        if let Some(midi_out) = self.midi_out.take() {
-            midi_out.close();
+        if self.midi_out.take().is_some() {
             self.chan_tx = None;
+            self.req_tx = None;
         }
     }
 
+    pub fn set_request_config(&mut self, config: RequestConfig) {
+        self.request_config = config;
+    }
+
+    /// Returns a `Requester` for the connected device.
+    ///
+    /// The `Requester` uses current sysex channel, so better get
+    /// a new one after a channel change.
+    pub fn requester(&self) -> Result<Requester, Error> {
+        let transport = self.midi_out.clone().ok_or(Error::MidiNotConnected)?;
+        let req_tx = self.req_tx.clone().ok_or(Error::MidiNotConnected)?;
+
+        Ok(Requester::new(
+            transport,
+            req_tx,
+            self.cc_chan,
+            self.sysex_chan,
+            self.request_config,
+        ))
+    }
+
     pub fn change_chan(&mut self, chan: midi::Channel) {
         // Note: I couldn't find the expected behaviour when changing the channel to All
         // in Utility Settings on the J-Station.
@@ -82,54 +113,26 @@ impl Interface {
         )
     }
 
-    pub fn have_who_am_i_resp(&mut self, resp: procedure::WhoAmIResp) -> Result<(), Error> {
+    pub fn have_who_am_i_resp(&mut self, resp: procedure::WhoAmIResp) {
         self.cc_chan = resp.receive_chan;
         self.sysex_chan = resp.sysex_chan;
-
-        log::debug!("Sending UtilitySettingsReq");
-        self.request_utility_settings()
     }
 
-    pub fn request_utility_settings(&mut self) -> Result<(), Error> {
-        self.send_sysex(procedure::UtilitySettingsReq)
-            .map_err(|err| Error::with_context("Utility Settings req.", err))
-    }
-
-    pub fn bank_dump(&mut self) -> Result<(), Error> {
-        self.send_sysex(procedure::BankDumpReq)
-            .map_err(|err| Error::with_context("Bank Dump req.", err))
-    }
-
-    pub fn program_update_req(&mut self) -> Result<(), Error> {
-        self.send_sysex(procedure::ProgramUpdateReq)
-            .map_err(|err| Error::with_context("Program Update req.", err))
+    /// Updates the device edit buffer with the provided `ProgramData`.
+    pub fn program_update(&mut self, prog_data: &ProgramData) -> Result<(), Error> {
+        self.send_sysex(procedure::ProgramUpdateResp::from_changed(prog_data))
+            .map_err(|err| Error::with_context("Program Update resp.", err))
     }
 
     pub fn change_program(&mut self, id: impl Into<midi::ProgramNumber>) -> Result<(), Error> {
         self.send(&midi::ProgramChange::build_for(id.into(), self.cc_chan))
     }
 
-    pub fn request_program(&mut self, id: jstation::ProgramId) -> Result<(), Error> {
-        self.send_sysex(procedure::OneProgramReq { id })
-            .map_err(|err| Error::with_context("Program req.", err))
-    }
-
     pub fn reload_program(&mut self) -> Result<(), Error> {
         self.send_sysex(procedure::ReloadProgramReq)
             .map_err(|err| Error::with_context("Reload Program req.", err))
     }
 
-    pub fn store_program(&mut self, prog: &Program) -> Result<(), Error> {
-        // Note: gstation-edit also sends a ProgramUpdateResp, but the
-        // result seems to be the same with only OneProgramResp.
-        // Or is it needed to change program's name?
-
-        // FIXME there's also StoreProgram procedure with id 0x21
-        // And NotifyStore is described as: Store Terminated
-        self.send_sysex(procedure::OneProgramResp::from(prog))
-            .map_err(|err| Error::with_context("Store One Program resp.", err))
-    }
-
     pub fn update_utility_settings(&mut self, settings: dsp::UtilitySettings) -> Result<(), Error> {
         let resp: procedure::UtilitySettingsResp = settings.into();
         self.send_sysex(resp)
@@ -145,32 +148,52 @@ impl Interface {
 
     fn send(&mut self, msg: &[u8]) -> Result<(), Error> {
         self.midi_out
-            .as_mut()
+            .as_ref()
             .ok_or(Error::MidiNotConnected)?
+            .lock()
+            .expect("transport poisoned")
             .send(msg)
-            .map_err(|_| Error::MidiSend)
     }
 
-    fn start_handshake(&mut self, midi_out: &mut midir::MidiOutputConnection) -> Result<(), Error> {
+    fn start_handshake(&mut self, midi_out: &mut dyn Transport) -> Result<(), Error> {
         self.cc_chan = midi::Channel::ALL;
         self.sysex_chan = midi::Channel::ALL;
 
         log::debug!("Sending WhoAmIReq");
-        midi_out
-            .send(&procedure::WhoAmIReq::default().build_for(midi::Channel::ALL))
-            .map_err(|_| Error::MidiSend)
+        midi_out.send(&procedure::WhoAmIReq::default().build_for(midi::Channel::ALL))
     }
 
-    pub fn connected_ports(&self) -> Option<(Arc<str>, Arc<str>)> {
-        self.ins.cur().zip(self.outs.cur())
+    /// Connects to a device using the provided `Transport` & `Incoming`.
+    ///
+    /// This can be used with a [`super::transport::loopback`] or a [`super::transport::Replay`].
+    pub fn connect_transport(
+        &mut self,
+        mut transport: impl Transport + 'static,
+        incoming: Incoming,
+    ) -> Result<Listener, Error> {
+        self.clear();
+
+        let listener = self.new_listener(incoming);
+
+        self.start_handshake(&mut transport)?;
+
+        self.midi_out = Some(Arc::new(Mutex::new(Box::new(transport))));
+
+        Ok(listener)
     }
-}
 
-impl Drop for Interface {
-    fn drop(&mut self) {
-        if let Some(midi_out) = self.midi_out.take() {
-            midi_out.close();
-        }
+    fn new_listener(&mut self, incoming: Incoming) -> Listener {
+        let (chan_tx, chan_rx) = mpsc::channel(1);
+        self.chan_tx = Some(chan_tx);
+
+        let (req_tx, req_rx) = mpsc::unbounded();
+        self.req_tx = Some(req_tx);
+
+        Listener::new(incoming, chan_rx, req_rx)
+    }
+
+    pub fn connected_ports(&self) -> Option<(Arc<str>, Arc<str>)> {
+        self.ins.cur().zip(self.outs.cur())
     }
 }
 
@@ -188,23 +211,21 @@ impl midi::Scannable for Interface {
     }
 
     fn connect(&mut self, port_in: Arc<str>, port_out: Arc<str>) -> Result<(Listener, ()), Error> {
-        let mut midi_out = self.outs.connect(port_out)?;
+        let mut midi_out = Midir::new(self.outs.connect(port_out)?);
 
-        let (chan_tx, chan_rx) = mpsc::channel(1);
-        let listener = Listener::try_new(self, port_in, chan_rx)?;
-        self.chan_tx = Some(chan_tx);
+        let incoming = Midir::connect_in(&mut self.ins, port_in)?;
+        let listener = self.new_listener(incoming);
 
         self.start_handshake(&mut midi_out)?;
 
-        self.midi_out = Some(midi_out);
+        self.midi_out = Some(Arc::new(Mutex::new(Box::new(midi_out))));
 
         Ok((listener, ()))
     }
 
     fn connect_in(&mut self, port_name: Arc<str>) -> Result<Listener, Error> {
-        let (chan_tx, chan_rx) = mpsc::channel(1);
-        let listener = Listener::try_new(self, port_name, chan_rx)?;
-        self.chan_tx = Some(chan_tx);
+        let incoming = Midir::connect_in(&mut self.ins, port_name)?;
+        let listener = self.new_listener(incoming);
 
         self.cc_chan = midi::Channel::ALL;
         self.sysex_chan = midi::Channel::ALL;
@@ -213,15 +234,12 @@ impl midi::Scannable for Interface {
     }
 
     fn connect_out(&mut self, port_name: Arc<str>) -> Result<(), Error> {
-        let mut midi_out = self.outs.connect(port_name)?;
-
-        if let Some(prev_midi_out) = self.midi_out.take() {
-            prev_midi_out.close();
-        }
+        let mut midi_out = Midir::new(self.outs.connect(port_name)?);
+        self.midi_out = None;
 
         self.start_handshake(&mut midi_out)?;
 
-        self.midi_out = Some(midi_out);
+        self.midi_out = Some(Arc::new(Mutex::new(Box::new(midi_out))));
 
         Ok(())
     }
@@ -237,30 +255,27 @@ pub struct Listener {
     state: ListenerState,
     cc_chan: midi::Channel,
     sysex_chan: midi::Channel,
-    msg_rx: mpsc::Receiver<Vec<u8>>,
-    midi_in: Option<midir::MidiInputConnection<mpsc::Sender<Vec<u8>>>>,
+    msg_rx: Incoming,
     chan_rx: mpsc::Receiver<midi::Channel>,
+    req_rx: mpsc::UnboundedReceiver<PendingRequest>,
+    pending_reqs: Vec<PendingRequest>,
 }
 
 impl Listener {
-    fn try_new(
-        iface: &mut Interface,
-        port_in: Arc<str>,
+    fn new(
+        msg_rx: Incoming,
         chan_rx: mpsc::Receiver<midi::Channel>,
-    ) -> Result<Self, Error> {
-        let (msg_tx, msg_rx) = mpsc::channel(10);
-        let midi_in = iface.ins.connect(port_in, msg_tx, |_ts, msg, msg_tx| {
-            let _ = msg_tx.try_send(msg.to_owned());
-        })?;
-
-        Ok(Listener {
+        req_rx: mpsc::UnboundedReceiver<PendingRequest>,
+    ) -> Self {
+        Listener {
             state: ListenerState::AwaitingHandshake,
             cc_chan: midi::Channel::default(),
             sysex_chan: midi::Channel::default(),
             msg_rx,
-            midi_in: Some(midi_in),
             chan_rx,
-        })
+            req_rx,
+            pending_reqs: Vec::new(),
+        }
     }
 
     pub async fn listen(&mut self) -> Result<Message, Error> {
@@ -293,6 +308,11 @@ impl Listener {
                     if sysex.chan == self.sysex_chan {
                         log::trace!("Received {:?}", sysex.proc);
 
+                        if self.resolve_requests(sysex)? {
+                            // Error acknowledgement reported to the `Requester`.
+                            continue;
+                        }
+
                         return Ok(msg);
                     }
 
@@ -302,6 +322,60 @@ impl Listener {
         }
     }
 
+    /// Resolves the pending requests matching `sysex`.
+    ///
+    /// An acknowledgement only resolves the oldest matching request,
+    /// since the device acknowledges the requests in order.
+    ///
+    /// Returns `true` if `sysex` is an error acknowledgement for a request.
+    ///
+    /// Returns an `Error::DeviceRequest` if `sysex` is an error acknowledgement
+    /// for a request its `Requester` gave up on, e.g. after a timeout.
+    fn resolve_requests(&mut self, sysex: &Arc<sysex::Message>) -> Result<bool, Error> {
+        self.purge_requests();
+
+        let is_ack = matches!(sysex.proc, Procedure::ToMessageResp(_));
+        let mut is_resolved = false;
+        let mut late_err = None;
+        for req in std::mem::take(&mut self.pending_reqs) {
+            if is_ack && is_resolved {
+                self.pending_reqs.push(req);
+                continue;
+            }
+
+            match req.try_resolve(sysex) {
+                Ok(None) => is_resolved = true,
+                Ok(Some(err)) => {
+                    is_resolved = true;
+                    late_err = Some(err);
+                }
+                Err(req) => self.pending_reqs.push(req),
+            }
+        }
+
+        if let Some(err) = late_err {
+            return Err(err);
+        }
+
+        Ok(is_resolved
+            && matches!(
+                sysex.proc,
+                Procedure::ToMessageResp(procedure::ToMessageResp { res: Err(_) })
+            ))
+    }
+
+    /// Registers `req` so it is resolved by the device messages.
+    fn add_pending_request(&mut self, req: PendingRequest) {
+        self.purge_requests();
+        self.pending_reqs.push(req);
+    }
+
+    /// Drops the requests given up on for which no late response is expected.
+    fn purge_requests(&mut self) {
+        let now = Instant::now();
+        self.pending_reqs.retain_mut(|req| !req.is_expired(now));
+    }
+
     /// Awaits for a device handshake response.
     ///
     /// Listens to all channels and returns the next `WhoAmIResp`.
@@ -348,7 +422,8 @@ impl Listener {
                         self.sysex_chan = chan;
                     }
                 }
-                msg_res = self.msg_rx.next() => break msg_res.expect("Broken message channel"),
+                req = self.req_rx.select_next_some() => self.add_pending_request(req),
+                msg_res = self.msg_rx.recv() => break msg_res.expect("Broken message channel"),
             }
         };
 
@@ -376,7 +451,7 @@ impl Listener {
         let mut timeout = futures::FutureExt::fuse(smol::Timer::after(HANDSHAKE_TIMEOUT));
 
         let midi_msg = futures::select_biased! {
-            res = self.msg_rx.next() => res.expect("Broken message channel"),
+            res = self.msg_rx.recv() => res.expect("Broken message channel"),
             _ = timeout => {
                 log::debug!("timeout");
                 return Err(Error::HandshakeTimeout);
@@ -392,11 +467,3 @@ impl Listener {
         Ok(proc)
     }
 }
-
-impl Drop for Listener {
-    fn drop(&mut self) {
-        if let Some(midi_in) = self.midi_in.take() {
-            midi_in.close();
-        }
-    }
-}
@@ -0,0 +1,265 @@
+use std::{
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::jstation::{
+    preset::{self, Metadata},
+    toml_file, Error, ProgramData,
+};
+
+const EXTENSION: &str = "toml";
+
+/// A Program stored in the `Library`.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    path: PathBuf,
+    data: ProgramData,
+    pub meta: Metadata,
+}
+
+impl Entry {
+    pub fn data(&self) -> &ProgramData {
+        &self.data
+    }
+
+    pub fn name(&self) -> &str {
+        self.data.name()
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Checks whether the `Entry` matches the provided filter.
+    ///
+    /// The filter is matched against the name, the author & the tags.
+    pub fn matches(&self, filter: &str) -> bool {
+        if filter.is_empty() {
+            return true;
+        }
+
+        let filter = filter.to_lowercase();
+
+        self.name().to_lowercase().contains(&filter)
+            || self.meta.author.to_lowercase().contains(&filter)
+            || self
+                .meta
+                .tags
+                .iter()
+                .any(|tag| tag.to_lowercase().contains(&filter))
+    }
+}
+
+/// An on-disk library of Programs, independent of the device's slots.
+///
+/// Each Program is stored as a text preset in the library directory.
+#[derive(Debug)]
+pub struct Library {
+    dir: PathBuf,
+    entries: Vec<Entry>,
+}
+
+impl Library {
+    /// Returns the default library directory for current user.
+    pub fn default_dir() -> Option<PathBuf> {
+        toml_file::default_path("library")
+    }
+
+    /// Opens the library at `dir`, creating the directory if needed.
+    ///
+    /// Files which can't be parsed are skipped.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self, Error> {
+        let dir = dir.into();
+        let ctx = || format!("Opening library {}", dir.display());
+
+        fs::create_dir_all(&dir).map_err(|err| Error::with_context(ctx(), err))?;
+
+        let mut entries = Vec::new();
+        for dir_entry in fs::read_dir(&dir).map_err(|err| Error::with_context(ctx(), err))? {
+            let path = dir_entry
+                .map_err(|err| Error::with_context(ctx(), err))?
+                .path();
+            if path.extension() != Some(OsStr::new(EXTENSION)) {
+                continue;
+            }
+
+            match Self::read(&path) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => log::warn!("Skipping {}: {err}", path.display()),
+            }
+        }
+
+        let mut library = Library { dir, entries };
+        library.sort();
+
+        Ok(library)
+    }
+
+    fn read(path: &Path) -> Result<Entry, Error> {
+        let input = fs::read_to_string(path)
+            .map_err(|err| Error::with_context(format!("Reading {}", path.display()), err))?;
+        let (data, meta) = preset::parse_with(&input)?;
+
+        Ok(Entry {
+            path: path.to_owned(),
+            data,
+            meta,
+        })
+    }
+
+    fn write(entry: &Entry) -> Result<(), Error> {
+        fs::write(
+            &entry.path,
+            preset::serialize_with(&entry.data, &entry.meta)?,
+        )
+        .map_err(|err| Error::with_context(format!("Writing {}", entry.path.display()), err))
+    }
+
+    fn sort(&mut self) {
+        self.entries
+            .sort_by_cached_key(|entry| entry.name().to_lowercase());
+    }
+
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    pub fn get(&self, idx: usize) -> Option<&Entry> {
+        self.entries.get(idx)
+    }
+
+    /// Returns the `Entry` stored at `path`.
+    ///
+    /// Unlike indices, paths are stable when entries are added or removed.
+    pub fn find(&self, path: &Path) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.path == path)
+    }
+
+    /// Returns a path for a new file in the library, based on `name`.
+    fn new_path(&self, name: &str) -> PathBuf {
+        let mut stem = String::from_iter(name.trim().chars().map(|c| {
+            if c.is_alphanumeric() || c == ' ' || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        }));
+        if stem.is_empty() {
+            stem = "program".to_string();
+        }
+
+        let mut path = self.dir.join(format!("{stem}.{EXTENSION}"));
+        let mut idx = 1;
+        while path.exists() {
+            idx += 1;
+            path = self.dir.join(format!("{stem} ({idx}).{EXTENSION}"));
+        }
+
+        path
+    }
+
+    fn find_mut(&mut self, path: &Path) -> Result<&mut Entry, Error> {
+        self.entries
+            .iter_mut()
+            .find(|entry| entry.path == path)
+            .ok_or_else(|| Error::LibraryEntryNotFound(path.to_owned()))
+    }
+
+    /// Adds a new `Entry` to the library.
+    ///
+    /// Returns the new `Entry`.
+    pub fn add(&mut self, data: ProgramData, meta: Metadata) -> Result<&Entry, Error> {
+        let entry = Entry {
+            path: self.new_path(data.name()),
+            data,
+            meta,
+        };
+
+        Self::write(&entry)?;
+
+        let path = entry.path.clone();
+        self.entries.push(entry);
+        self.sort();
+
+        Ok(self.find(&path).expect("entry just added"))
+    }
+
+    /// Updates the `Metadata` for the `Entry` stored at `path`.
+    pub fn update_meta(&mut self, path: &Path, meta: Metadata) -> Result<(), Error> {
+        let entry = self.find_mut(path)?;
+        entry.meta = meta;
+
+        Self::write(entry)
+    }
+
+    /// Removes the `Entry` stored at `path` from the library.
+    pub fn remove(&mut self, path: &Path) -> Result<(), Error> {
+        self.find_mut(path)?;
+        fs::remove_file(path)
+            .map_err(|err| Error::with_context(format!("Removing {}", path.display()), err))?;
+
+        self.entries.retain(|entry| entry.path != path);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Library, Metadata};
+    use crate::jstation::{Error, ProgramData};
+
+    #[test]
+    fn add_update_remove() {
+        let dir = std::env::temp_dir().join(format!("jstation-library-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let mut library = Library::open(&dir).unwrap();
+        assert!(library.entries().is_empty());
+
+        let mut data = ProgramData::default();
+        data.store_name("Lead/Solo");
+        library.add(data.clone(), Metadata::default()).unwrap();
+        // Same name
+        library.add(data, Metadata::default()).unwrap();
+
+        let mut data = ProgramData::default();
+        data.store_name("Clean");
+        let clean = library
+            .add(data, Metadata::default())
+            .unwrap()
+            .path()
+            .to_owned();
+        assert_eq!(library.get(0).unwrap().path(), clean);
+
+        let meta = Metadata {
+            author: "Me".to_string(),
+            tags: vec!["live".to_string()],
+            notes: String::new(),
+        };
+        library.update_meta(&clean, meta.clone()).unwrap();
+
+        let mut library = Library::open(&dir).unwrap();
+        assert_eq!(library.entries().len(), 3);
+        assert_eq!(library.get(0).unwrap().name(), "Clean");
+        assert_eq!(library.get(0).unwrap().meta, meta);
+        assert!(library.get(0).unwrap().matches("LIVE"));
+        assert!(!library.get(1).unwrap().matches("live"));
+        assert_eq!(library.get(1).unwrap().name(), "Lead/Solo");
+        assert_eq!(library.get(2).unwrap().name(), "Lead/Solo");
+
+        library.remove(&clean).unwrap();
+        assert_eq!(Library::open(&dir).unwrap().entries().len(), 2);
+
+        // Stale path
+        assert!(matches!(
+            library.remove(&clean),
+            Err(Error::LibraryEntryNotFound(path)) if path == clean,
+        ));
+        assert!(library.update_meta(&clean, meta).is_err());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -87,6 +87,8 @@ impl BufferBuilder {
 pub struct Message {
     pub chan: midi::Channel,
     pub proc: Procedure,
+    /// Length of the raw message.
+    pub len: usize,
 }
 
 pub fn parse(input: &[u8]) -> IResult<&[u8], Message> {
@@ -120,6 +122,7 @@ pub fn parse(input: &[u8]) -> IResult<&[u8], Message> {
         Message {
             chan: midi::Channel::from(chan[0]),
             proc,
+            len: input.len() - i.len(),
         },
     ))
 }
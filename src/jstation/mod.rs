@@ -1,9 +1,14 @@
 pub mod data;
 pub use data::{dsp, CCParameter, Program, ProgramData, ProgramId, ProgramNb, ProgramsBank};
 
+pub mod controller;
+
 mod error;
 pub use error::Error;
 
+mod history;
+pub use history::{Edit, History};
+
 mod interface;
 pub use interface::{Interface, Listener};
 
@@ -13,6 +18,29 @@ pub use midi::*;
 pub mod procedure;
 pub use procedure::{Procedure, ProcedureBuilder, ProcedureId};
 
+pub mod library;
+pub use library::Library;
+
+pub mod organizer;
+pub use organizer::Arrangement;
+
+pub mod preset;
+
+pub mod request;
+pub use request::{Operation, RequestConfig, Requester, StoreBatch};
+
+pub mod setlist;
+pub use setlist::{Setlist, Setlists, Song};
+
+pub mod simulator;
+
+pub mod syx;
+
+mod toml_file;
+
+pub mod transport;
+pub use transport::Transport;
+
 pub mod prelude {
     pub use super::data::{
         BoolParameter, CCParameterSetter, ConstRangeParameter, DiscreteParameter, ParameterSetter,
@@ -21,7 +49,11 @@ pub mod prelude {
     pub use super::JStationImpl;
 }
 
-use std::{collections::BTreeMap, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+    sync::Arc,
+};
 
 use prelude::*;
 
@@ -30,10 +62,86 @@ pub struct JStation {
     dsp: dsp::Dsp,
     bank: ProgramsBank,
     programs: BTreeMap<ProgramId, Program>,
+    pending_bank: Vec<Program>,
+    bank_dump: Option<BankDumpProgress>,
+    /// The `Program`s still expected after the bank dump, see [`Requester::sync_programs`].
+    fetch: Option<ProgramsFetch>,
+    /// `Program`s which couldn't be read from the device.
+    unreadable: BTreeSet<ProgramId>,
+    /// The populated user `Program`s, if known.
+    populated: Option<BTreeSet<ProgramNb>>,
+    /// The user `Program`s stored or imported while no device was connected.
+    offline_changes: BTreeSet<ProgramId>,
     cur_prog_id: Option<ProgramId>,
+    /// Edits of the current `Program`.
+    history: History,
+    /// The edited `Dsp` while it is compared with the stored `Program`.
+    edited: Option<dsp::Dsp>,
+    /// The last `Program` selected on the device, as far as we know.
+    last_prog_change: Option<ProgramId>,
+    /// Whether the device edit buffer was received.
+    has_edit_buffer: bool,
     has_changed: bool,
 }
 
+/// Progress of a bank dump.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct BankDumpProgress {
+    /// Length of the `Program`s messages, as announced by the device.
+    pub total_len: usize,
+    /// Length of the `Program`s messages received so far.
+    pub received_len: usize,
+    /// Number of `Program`s received so far.
+    pub received: usize,
+}
+
+impl BankDumpProgress {
+    /// Returns the ratio of the received data in the range `0.0..=1.0`.
+    pub fn ratio(&self) -> f32 {
+        if self.total_len == 0 {
+            return 0.0;
+        }
+
+        (self.received_len as f32 / self.total_len as f32).min(1.0)
+    }
+
+    /// Returns the expected number of `Program`s.
+    ///
+    /// This is estimated from the `Program`s received so far.
+    pub fn expected(&self) -> usize {
+        if self.received_len == 0 {
+            return ProgramNb::enumerate().count();
+        }
+
+        (self.total_len * self.received + self.received_len / 2) / self.received_len
+    }
+}
+
+/// Progress of the `Program`s requested one by one after a bank dump.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FetchProgress {
+    /// Number of `Program`s received so far.
+    pub received: usize,
+    pub expected: usize,
+}
+
+impl FetchProgress {
+    /// Returns the ratio of the received `Program`s in the range `0.0..=1.0`.
+    pub fn ratio(&self) -> f32 {
+        if self.expected == 0 {
+            return 0.0;
+        }
+
+        (self.received as f32 / self.expected as f32).min(1.0)
+    }
+}
+
+/// The `Program`s requested one by one after a bank dump.
+struct ProgramsFetch {
+    remaining: BTreeSet<ProgramId>,
+    expected: usize,
+}
+
 impl JStation {
     pub fn new(app_name: Arc<str>) -> Self {
         JStation {
@@ -41,31 +149,169 @@ impl JStation {
             dsp: dsp::Dsp::default(),
             bank: ProgramsBank::default(),
             programs: BTreeMap::new(),
+            pending_bank: Vec::new(),
+            bank_dump: None,
+            fetch: None,
+            unreadable: BTreeSet::new(),
+            populated: None,
+            offline_changes: BTreeSet::new(),
             cur_prog_id: None,
+            history: History::default(),
+            edited: None,
+            last_prog_change: None,
+            has_edit_buffer: false,
             has_changed: false,
         }
     }
 
+    /// Loads the `Program` `prog_id` in the local edit buffer.
+    ///
+    /// When connected, unknown `Program`s must be requested
+    /// using [`Requester::request_program`].
     fn load_prog(&mut self, prog_id: ProgramId) -> Result<(), Error> {
+        self.history.clear();
+        self.edited = None;
+
         if let Some(prog) = self.programs.get(&prog_id) {
             self.dsp.set_from(prog.data()).unwrap();
-        } else if let Err(err) = self.iface.request_program(prog_id) {
-            self.cur_prog_id = None;
+        } else if !self.iface.is_connected() {
+            // Offline: start from a default Program.
+            self.dsp.set_from(&ProgramData::default())?;
+        }
 
-            return Err(err);
+        Ok(())
+    }
+
+    /// Stores the edit buffer to the user `Program` `prog_id` without a device.
+    fn store_offline(&mut self, prog_id: ProgramId) {
+        let prog = self
+            .programs
+            .entry(prog_id)
+            .or_insert_with(|| Program::new(prog_id, ProgramData::default()));
+        self.dsp.store(prog.data_mut());
+
+        self.bank = ProgramsBank::User;
+        self.cur_prog_id = Some(prog_id);
+        self.has_changed = false;
+        self.offline_changes.insert(prog_id);
+    }
+
+    /// Identifies the `Program` the device edit buffer originates from.
+    ///
+    /// The J-Station can't report its active `Program` number, so it is
+    /// deduced from the last `ProgramChange` & the edit buffer content.
+    /// Returns `None` if the origin is unknown or ambiguous.
+    fn identify_cur_prog(&self, has_changed: bool) -> Option<ProgramId> {
+        // Fall back to the `Program` identified previously, e.g. by content.
+        if let Some(prog_id) = self.last_prog_change.or(self.cur_prog_id) {
+            let is_consistent = match self.programs.get(&prog_id) {
+                Some(prog) => has_changed || !self.dsp.has_changed(prog.data()),
+                // Not received yet.
+                None => true,
+            };
+
+            if is_consistent {
+                return Some(prog_id);
+            }
+
+            log::debug!("Edit buffer doesn't originate from last Program change {prog_id}");
+        }
+
+        if has_changed {
+            return None;
+        }
+
+        let matches = Vec::from_iter(
+            self.programs
+                .values()
+                .filter(|prog| !self.dsp.has_changed(prog.data()))
+                .map(Program::id),
+        );
+
+        // User Programs are often copies of factory Programs:
+        // several matches can't tell which one was selected.
+        match matches.as_slice() {
+            [prog_id] => Some(*prog_id),
+            [] => None,
+            _ => {
+                log::debug!("Edit buffer matches several Programs: {matches:?}");
+                None
+            }
+        }
+    }
+
+    /// Sends `param` to the device as a CC.
+    fn send_param(&mut self, param: dsp::Parameter) -> Result<(), Error> {
+        // Offline: local changes only.
+        if !self.iface.is_connected() {
+            return Ok(());
+        }
+
+        match param.to_cc() {
+            Some(cc) => self.iface.send_cc(cc)?,
+            None => log::error!("No CC for {:?}", param),
         }
 
         Ok(())
     }
 
+    /// Applies `edit` to the edit buffer, e.g. when undoing an `Edit`.
+    fn apply_edit(&mut self, edit: Edit) -> Result<(), Error> {
+        match edit {
+            Edit::Parameter { new, .. } => {
+                if let Some(new) = self.dsp.set(new) {
+                    self.send_param(new)?;
+                }
+            }
+            Edit::Rename { new, .. } => self.dsp.name = new,
+            Edit::Block { new, .. } => {
+                self.set_block(new)?;
+            }
+        }
+
+        self.update_has_changed();
+
+        Ok(())
+    }
+
+    /// Sets the `block` parameters of the edit buffer, sending the changed ones to the device.
+    ///
+    /// Returns `true` if a parameter changed.
+    fn set_block(&mut self, block: dsp::Block) -> Result<bool, Error> {
+        let mut has_changed = false;
+        for param in block.params() {
+            if let Some(new) = self.dsp.set(param) {
+                self.send_param(new)?;
+                has_changed = true;
+            }
+        }
+
+        Ok(has_changed)
+    }
+
     fn update_has_changed(&mut self) {
         let cur_prog = self
             .cur_prog_id
             .and_then(|prog_id| self.programs.get(&prog_id));
         if let Some(cur_prog) = cur_prog {
-            self.has_changed = self.dsp.has_changed(cur_prog.data());
+            let dsp = self.edited.as_ref().unwrap_or(&self.dsp);
+            self.has_changed = dsp.has_changed(cur_prog.data());
         }
     }
+
+    /// Restores the edited `Dsp` if it is being compared with the stored `Program`.
+    fn end_compare(&mut self) -> Result<(), Error> {
+        let Some(edited) = self.edited.take() else {
+            return Ok(());
+        };
+
+        self.dsp = edited;
+        if self.iface.is_connected() {
+            self.iface.program_update(self.edit_buffer().data())?;
+        }
+
+        Ok(())
+    }
 }
 
 impl JStationImpl for JStation {
@@ -110,8 +356,21 @@ impl JStationImpl for JStation {
     fn clear(&mut self) {
         self.iface.clear();
         self.bank = ProgramsBank::default();
-        self.programs.clear();
+        if self.offline_changes.is_empty() {
+            // Keep the offline changes so they can be synced to a device later.
+            // The factory `Program`s can't change, no need to request them again.
+            self.programs
+                .retain(|prog_id, _| prog_id.bank().is_factory());
+        }
+        self.bank_dump = None;
+        self.fetch = None;
+        self.unreadable.clear();
+        self.populated = None;
         self.cur_prog_id = None;
+        self.history.clear();
+        self.edited = None;
+        self.last_prog_change = None;
+        self.has_edit_buffer = false;
         self.has_changed = false;
     }
 
@@ -120,72 +379,136 @@ impl JStationImpl for JStation {
         match msg {
             SysEx(sysex) => {
                 use Procedure::*;
-                match Arc::try_unwrap(sysex).unwrap().proc {
+                match &sysex.proc {
                     NotifyStore(resp) => {
-                        let prog_id = ProgramId::new_user(resp.nb);
-                        self.cur_prog_id = Some(prog_id);
-                        self.iface.request_program(prog_id).expect("Not connected");
+                        // The stored `Program` must be requested.
+                        self.cur_prog_id = Some(ProgramId::new_user(resp.nb));
+                        self.last_prog_change = self.cur_prog_id;
                         self.has_changed = false;
+                        if let Some(populated) = self.populated.as_mut() {
+                            populated.insert(resp.nb);
+                        }
                     }
                     NotifyUtility(_) => {
-                        self.iface
-                            .request_utility_settings()
-                            .expect("Not connected");
+                        log::debug!("Utility Settings changed on device");
                     }
                     WhoAmIResp(resp) => {
-                        self.programs.clear();
-
-                        self.iface.have_who_am_i_resp(resp).map_err(|err| {
-                            self.clear();
+                        if !self.offline_changes.is_empty() {
+                            // Offer to sync the offline changes once the device bank is received.
+                            self.pending_bank = Vec::from_iter(
+                                std::mem::take(&mut self.offline_changes)
+                                    .into_iter()
+                                    .filter_map(|prog_id| self.programs.remove(&prog_id)),
+                            );
+                        }
 
-                            err
-                        })?;
+                        // Keep the factory `Program`s, see `Self::cached_programs`.
+                        self.programs
+                            .retain(|prog_id, _| prog_id.bank().is_factory());
+                        self.iface.have_who_am_i_resp(*resp);
                     }
                     UtilitySettingsResp(resp) => {
                         self.dsp.utility_settings = resp.try_into()?;
-
-                        if self.programs.is_empty() {
-                            self.iface.bank_dump()?;
-                        }
                         // When changing channel from the J-Station,
                         // `NotifyUtility` is sent on the new channel.
                         // Better change the channel from the application instead.
                     }
-                    ProgramIndicesResp(_) => (),
+                    ProgramIndicesResp(resp) => {
+                        self.populated = Some(BTreeSet::from_iter(resp.numbers.iter().copied()));
+                    }
                     OneProgramResp(resp) => {
-                        if self.cur_prog_id.is_some() {
+                        let prog_id = resp.prog.id();
+                        if self.cur_prog_id == Some(prog_id) && !self.has_changed {
+                            // The current Program was unknown or is refreshed.
                             self.dsp.set_from(resp.prog.data())?;
+                            self.history.clear();
+                            self.edited = None;
+                        }
+
+                        self.unreadable.remove(&prog_id);
+                        if let Some(populated) = self.populated.as_mut() {
+                            if prog_id.bank().is_user() {
+                                populated.insert(prog_id.nb());
+                            }
+                        }
+                        self.programs.insert(prog_id, resp.prog.clone());
+
+                        // No need to store pending `Program`s already on the device.
+                        self.pending_bank.retain(|pending| {
+                            pending.id() != prog_id || pending.data() != resp.prog.data()
+                        });
+
+                        if let Some(bank_dump) = self.bank_dump.as_mut() {
+                            bank_dump.received += 1;
+                            bank_dump.received_len += sysex.len;
                         }
 
-                        self.programs.insert(resp.prog.id(), resp.prog);
+                        if let Some(fetch) = self.fetch.as_mut() {
+                            fetch.remaining.remove(&prog_id);
+                            if fetch.remaining.is_empty() {
+                                self.fetch = None;
+                            }
+                        }
                     }
                     ProgramUpdateResp(resp) => {
-                        self.dsp.set_from(&resp.prog_data)?;
+                        if self.dsp.has_changed(&resp.prog_data) {
+                            self.dsp.set_from(&resp.prog_data)?;
+                            self.history.clear();
+                            self.edited = None;
+                        }
                         self.has_changed = resp.has_changed;
+                        self.has_edit_buffer = true;
+                        // The edit buffer is requested once the `Program`s are synced.
+                        self.fetch = None;
 
-                        let prog = self
-                            .programs
-                            .iter()
-                            .find(|(_, prog)| !self.dsp.has_changed(prog.data()));
-
-                        if let Some((_, prog)) = prog {
-                            self.cur_prog_id = Some(prog.id());
+                        self.cur_prog_id = self.identify_cur_prog(resp.has_changed);
+                        if let Some(prog_id) = self.cur_prog_id {
+                            self.bank = prog_id.bank();
                         } else {
-                            // This can occur on startup when the program on device `has_changed`
-                            // or if a factory program is selected.
-                            self.cur_prog_id = None;
-                            return Err(Error::ProgramIdenticationFailure);
+                            log::debug!("Edit buffer origin unknown");
                         }
                     }
-                    StartBankDumpResp(_) => {
+                    StartBankDumpResp(resp) => {
                         self.bank = ProgramsBank::default();
+                        self.bank_dump = Some(BankDumpProgress {
+                            total_len: resp.total_len.into(),
+                            ..Default::default()
+                        });
                     }
                     EndBankDumpResp(_) => {
-                        self.iface.program_update_req()?;
+                        if let Some(bank_dump) = self.bank_dump.take() {
+                            log::debug!(
+                                "Bank dump: received {} Programs, expected {}",
+                                bank_dump.received,
+                                bank_dump.expected(),
+                            );
+                        }
+
+                        // The factory `Program`s & the user `Program`s missing
+                        // from the bank dump are then requested one by one.
+                        let remaining =
+                            BTreeSet::from_iter(ProgramId::enumerate().filter(|prog_id| {
+                                !self.programs.contains_key(prog_id)
+                                    && (prog_id.bank().is_factory()
+                                        || self.populated.as_ref().is_some_and(|populated| {
+                                            populated.contains(&prog_id.nb())
+                                        }))
+                            }));
+                        if !remaining.is_empty() {
+                            self.fetch = Some(ProgramsFetch {
+                                expected: remaining.len(),
+                                remaining,
+                            });
+                        }
                     }
                     ToMessageResp(resp) => match resp.res {
                         Ok(req_proc) => log::debug!("Proc. x{req_proc:02x}: Ok"),
-                        Err(err) => panic!("{err}"),
+                        Err(err) => {
+                            return Err(Error::DeviceRequest {
+                                op: Operation::from_req_proc(err.req_proc()),
+                                source: err,
+                            });
+                        }
                     },
                     other => {
                         log::debug!("Unhandled {other:?}");
@@ -202,6 +525,8 @@ impl JStationImpl for JStation {
                     },
                     ProgramChange(prog_id) => {
                         self.cur_prog_id = Some(prog_id);
+                        self.last_prog_change = Some(prog_id);
+                        self.has_changed = false;
                         self.bank = prog_id.bank();
 
                         self.load_prog(prog_id)?;
@@ -214,9 +539,12 @@ impl JStationImpl for JStation {
     }
 
     fn change_program(&mut self, id: ProgramId) -> Result<(), Error> {
-        self.iface.change_program(id)?;
+        if self.iface.is_connected() {
+            self.iface.change_program(id)?;
+        }
 
         self.cur_prog_id = Some(id);
+        self.last_prog_change = Some(id);
         self.has_changed = false;
 
         self.load_prog(id)?;
@@ -224,8 +552,14 @@ impl JStationImpl for JStation {
         Ok(())
     }
 
-    fn store_to(&mut self, nb: ProgramNb) -> Result<(), Error> {
+    fn store_to(&mut self, nb: ProgramNb) -> Result<StoreBatch, Error> {
         let prog_id = ProgramId::new_user(nb);
+        self.end_compare()?;
+
+        if !self.iface.is_connected() {
+            self.store_offline(prog_id);
+            return Ok(StoreBatch::default());
+        }
 
         if self
             .cur_prog_id
@@ -236,39 +570,135 @@ impl JStationImpl for JStation {
                 .expect("Changing to known user program");
             self.bank = ProgramsBank::User;
             self.cur_prog_id = Some(prog_id);
+            self.last_prog_change = Some(prog_id);
         }
 
-        let prog = self
-            .programs
-            .get_mut(&prog_id)
-            .expect("Storing to selected and known program");
+        // The slot might be empty.
+        let prog = self.programs.get(&prog_id);
 
-        if self.dsp.has_changed(prog.data()) {
-            self.dsp.store(prog.data_mut());
+        let has_changed = match prog {
+            Some(prog) => self.dsp.has_changed(prog.data()),
+            None => true,
+        };
 
-            // Technically, the program is actually stored on
-            // device after reception of the Ok ack for proc x02.
-            self.iface.store_program(prog)?;
+        let mut batch = StoreBatch::default();
+        if has_changed {
+            let mut data = prog.map_or_else(ProgramData::default, |prog| prog.data().clone());
+            self.dsp.store(&mut data);
 
-            self.has_changed = false;
+            // `programs` & `has_changed` are updated once the device stored the `Program`.
+            batch.progs.push(Program::new(prog_id, data));
         }
 
-        Ok(())
+        Ok(batch)
     }
 
-    fn undo(&mut self) -> Result<(), Error> {
-        self.iface.reload_program()?;
+    fn copy_to_user(&mut self, prog_id: ProgramId, nb: ProgramNb) -> Result<StoreBatch, Error> {
+        let data = self
+            .programs
+            .get(&prog_id)
+            .ok_or(Error::ProgramUnknown(prog_id))?
+            .data()
+            .clone();
+
+        self.send_to_edit_buffer(&data)?;
+        self.store_to(nb)
+    }
+
+    fn revert(&mut self) -> Result<(), Error> {
+        if self.iface.is_connected() {
+            self.iface.reload_program()?;
+        }
         if let Some(cur_prog_id) = self.cur_prog_id {
             self.load_prog(cur_prog_id)?;
         }
 
+        self.history.clear();
+        self.edited = None;
         self.has_changed = false;
 
         Ok(())
     }
 
+    fn toggle_compare(&mut self) -> Result<(), Error> {
+        if self.edited.is_some() {
+            return self.end_compare();
+        }
+
+        let Some(prog) = self
+            .cur_prog_id
+            .and_then(|prog_id| self.programs.get(&prog_id))
+        else {
+            return Ok(());
+        };
+
+        if !self.has_changed {
+            return Ok(());
+        }
+
+        if self.iface.is_connected() {
+            self.iface.program_update(prog.data())?;
+        }
+
+        let mut stored = self.dsp.clone();
+        stored.set_from(prog.data())?;
+        self.edited = Some(std::mem::replace(&mut self.dsp, stored));
+
+        Ok(())
+    }
+
+    fn is_comparing(&self) -> bool {
+        self.edited.is_some()
+    }
+
+    fn undo(&mut self) -> Result<(), Error> {
+        self.end_compare()?;
+        match self.history.undo() {
+            Some(edit) => self.apply_edit(edit),
+            None => Ok(()),
+        }
+    }
+
+    fn redo(&mut self) -> Result<(), Error> {
+        self.end_compare()?;
+        match self.history.redo() {
+            Some(edit) => self.apply_edit(edit),
+            None => Ok(()),
+        }
+    }
+
+    fn can_undo(&self) -> bool {
+        self.history.can_undo()
+    }
+
+    fn can_redo(&self) -> bool {
+        self.history.can_redo()
+    }
+
+    fn paste_block(&mut self, block: dsp::Block) -> Result<(), Error> {
+        self.end_compare()?;
+
+        let prev = self.dsp.block(block.kind());
+        if self.set_block(block)? {
+            self.history.push(Edit::Block { prev, new: block });
+            self.update_has_changed();
+        }
+
+        Ok(())
+    }
+
     fn rename(&mut self, name: impl ToString) {
-        self.dsp.name = ProgramData::format_name(name.to_string());
+        if let Err(err) = self.end_compare() {
+            log::warn!("{err}");
+        }
+
+        let name = ProgramData::format_name(name.to_string());
+        if name == self.dsp.name {
+            return;
+        }
+
+        let prev = std::mem::replace(&mut self.dsp.name, name.clone());
+        self.history.push(Edit::Rename { prev, new: name });
         self.update_has_changed();
     }
 
@@ -276,14 +706,196 @@ impl JStationImpl for JStation {
         self.bank = bank;
     }
 
-    fn update_param(&mut self, param: dsp::Parameter) {
-        if self.dsp.set(param).is_some() {
-            if let Some(cc) = param.to_cc() {
-                // FIXME handle the error
-                let _ = self.iface.send_cc(cc);
+    fn export_bank(&self, path: &Path) -> Result<(), Error> {
+        syx::export_bank(path, self.programs.values())
+    }
+
+    fn import_bank(&mut self, path: &Path) -> Result<(), Error> {
+        let mut progs = syx::import_bank(path)?;
+        progs.retain(|prog| {
+            if prog.id().bank().is_factory() {
+                log::debug!("Ignoring factory Program {}", prog.id());
+                return false;
+            }
+
+            true
+        });
+
+        if progs.is_empty() {
+            return Err(Error::ProgramsNotFound);
+        }
+
+        self.pending_bank = progs;
+
+        Ok(())
+    }
+
+    fn pending_bank(&self) -> &[Program] {
+        &self.pending_bank
+    }
+
+    fn rearrange_bank(&mut self, arrangement: &Arrangement) {
+        self.pending_bank = arrangement.programs(|prog_id| self.programs.get(&prog_id));
+    }
+
+    fn bank_dump_progress(&self) -> Option<BankDumpProgress> {
+        self.bank_dump
+    }
+
+    fn fetch_progress(&self) -> Option<FetchProgress> {
+        self.fetch.as_ref().map(|fetch| FetchProgress {
+            received: fetch.expected - fetch.remaining.len(),
+            expected: fetch.expected,
+        })
+    }
+
+    fn cached_programs(&self) -> Vec<ProgramId> {
+        Vec::from_iter(
+            self.programs
+                .keys()
+                .copied()
+                .filter(|prog_id| prog_id.bank().is_factory()),
+        )
+    }
+
+    fn is_origin_unknown(&self) -> bool {
+        self.has_edit_buffer && self.cur_prog_id.is_none()
+    }
+
+    fn is_unreadable(&self, prog_id: ProgramId) -> bool {
+        self.unreadable.contains(&prog_id)
+    }
+
+    fn is_empty_slot(&self, prog_id: ProgramId) -> bool {
+        match &self.populated {
+            Some(populated) => prog_id.bank().is_user() && !populated.contains(&prog_id.nb()),
+            None => false,
+        }
+    }
+
+    fn add_unreadable(&mut self, prog_ids: impl IntoIterator<Item = ProgramId>) {
+        self.fetch = None;
+        self.unreadable.extend(
+            prog_ids
+                .into_iter()
+                .filter(|prog_id| !self.programs.contains_key(prog_id)),
+        );
+    }
+
+    fn store_pending_bank(&mut self) -> Result<StoreBatch, Error> {
+        let is_connected = self.iface.is_connected();
+        // The `Program`s are stored through the edit buffer.
+        let edit_buffer = self.edit_buffer();
+
+        let mut batch = StoreBatch::default();
+        for prog in std::mem::take(&mut self.pending_bank) {
+            if self.cur_prog_id == Some(prog.id()) {
+                // The device uses the stored version once restored below.
+                self.dsp.set_from(prog.data())?;
+                self.history.clear();
+                self.edited = None;
+                self.has_changed = false;
+            }
+
+            if is_connected {
+                // `programs` is updated once the device stored the `Program`.
+                batch.progs.push(prog);
             } else {
-                log::error!("No CC for {:?}", param);
+                self.offline_changes.insert(prog.id());
+                self.programs.insert(prog.id(), prog);
+            }
+        }
+
+        if is_connected {
+            // Restore the edit buffer used for the stores.
+            batch.change_to = self.cur_prog_id;
+            if self.has_changed || self.cur_prog_id.is_none() {
+                batch.edit_buffer = Some(edit_buffer.data().clone());
             }
+        }
+
+        Ok(batch)
+    }
+
+    fn have_stored(&mut self, progs: Vec<Program>) {
+        for prog in progs {
+            let prog_id = prog.id();
+            log::debug!("Stored Program {prog_id}");
+
+            self.programs.insert(prog_id, prog);
+            if let Some(populated) = self.populated.as_mut() {
+                populated.insert(prog_id.nb());
+            }
+            if self.cur_prog_id == Some(prog_id) {
+                self.update_has_changed();
+            }
+        }
+    }
+
+    fn discard_pending_bank(&mut self) {
+        self.pending_bank.clear();
+    }
+
+    fn edit_buffer(&self) -> Program {
+        let cur_prog = self
+            .cur_prog_id
+            .and_then(|prog_id| self.programs.get(&prog_id));
+
+        // Start from current Program so as to keep the values
+        // which don't apply to the current `Dsp` configuration.
+        let mut data = cur_prog.map_or_else(ProgramData::default, |prog| prog.data().clone());
+        self.edited
+            .as_ref()
+            .unwrap_or(&self.dsp)
+            .clone()
+            .store(&mut data);
+
+        Program::new(self.cur_prog_id.unwrap_or_default(), data)
+    }
+
+    fn export_program(&self, path: &Path) -> Result<(), Error> {
+        syx::export_program(path, &self.edit_buffer())
+    }
+
+    fn import_program(&mut self, path: &Path) -> Result<(), Error> {
+        let prog = syx::import_program(path)?;
+        self.send_to_edit_buffer(prog.data())
+    }
+
+    fn export_preset(&self, path: &Path) -> Result<(), Error> {
+        preset::export(path, self.edit_buffer().data())
+    }
+
+    fn import_preset(&mut self, path: &Path) -> Result<(), Error> {
+        let data = preset::import(path)?;
+        self.send_to_edit_buffer(&data)
+    }
+
+    fn send_to_edit_buffer(&mut self, data: &ProgramData) -> Result<(), Error> {
+        if self.iface.is_connected() {
+            self.iface.program_update(data)?;
+        }
+
+        self.dsp.set_from(data)?;
+        self.history.clear();
+        self.edited = None;
+        // The Program is pending until it is stored.
+        self.has_changed = true;
+
+        Ok(())
+    }
+
+    fn update_param(&mut self, param: dsp::Parameter) {
+        if let Err(err) = self.end_compare() {
+            log::warn!("{err}");
+        }
+
+        let prev = self.dsp.get(param);
+        if let Some(new) = self.dsp.set(param) {
+            self.history.push(Edit::Parameter { prev, new });
+
+            // FIXME handle the error
+            let _ = self.send_param(new);
 
             self.update_has_changed();
         }
@@ -366,14 +978,68 @@ pub trait JStationImpl {
         self.inner_mut().change_program(id)
     }
 
-    fn store_to(&mut self, nb: ProgramNb) -> Result<(), Error> {
+    /// Stores the edit buffer to the user `Program` `nb`.
+    ///
+    /// When no device is connected, only the local `Program` is updated.
+    /// Otherwise, returns the `Program` to store using [`Requester::store_programs`].
+    fn store_to(&mut self, nb: ProgramNb) -> Result<StoreBatch, Error> {
         self.inner_mut().store_to(nb)
     }
 
+    /// Uses the `Program` `prog_id`, e.g. a factory `Program`, as the basis
+    /// for the user `Program` `nb`.
+    ///
+    /// The `Program` is stored to `nb` which becomes the current `Program`.
+    /// See [`Self::store_to`].
+    fn copy_to_user(&mut self, prog_id: ProgramId, nb: ProgramNb) -> Result<StoreBatch, Error> {
+        self.inner_mut().copy_to_user(prog_id, nb)
+    }
+
+    /// Reverts the edit buffer to the stored `Program`.
+    fn revert(&mut self) -> Result<(), Error> {
+        self.inner_mut().revert()
+    }
+
+    /// Switches the device between the stored version of the current `Program`
+    /// and the edit buffer.
+    ///
+    /// While the stored version is compared, `dsp()` reflects it and the
+    /// edit buffer is kept aside. Editing restores the edit buffer.
+    fn toggle_compare(&mut self) -> Result<(), Error> {
+        self.inner_mut().toggle_compare()
+    }
+
+    fn is_comparing(&self) -> bool {
+        self.inner().is_comparing()
+    }
+
+    /// Undoes the last edit of the current `Program`.
+    ///
+    /// Changed parameters are sent to the device.
     fn undo(&mut self) -> Result<(), Error> {
         self.inner_mut().undo()
     }
 
+    /// Applies the last undone edit again.
+    fn redo(&mut self) -> Result<(), Error> {
+        self.inner_mut().redo()
+    }
+
+    fn can_undo(&self) -> bool {
+        self.inner().can_undo()
+    }
+
+    fn can_redo(&self) -> bool {
+        self.inner().can_redo()
+    }
+
+    /// Replaces a DSP block of the edit buffer with `block`, e.g. copied from another `Program`.
+    ///
+    /// Changed parameters are sent to the device.
+    fn paste_block(&mut self, block: dsp::Block) -> Result<(), Error> {
+        self.inner_mut().paste_block(block)
+    }
+
     fn rename(&mut self, name: impl ToString) {
         self.inner_mut().rename(name);
     }
@@ -382,6 +1048,134 @@ pub trait JStationImpl {
         self.inner_mut().select_bank(bank);
     }
 
+    /// Exports the known `Program`s as a bank dump to the `.syx` file at `path`.
+    fn export_bank(&self, path: &Path) -> Result<(), Error> {
+        self.inner().export_bank(path)
+    }
+
+    /// Imports the user `Program`s from the bank dump in the `.syx` file at `path`.
+    ///
+    /// The `Program`s are kept pending until [`Self::store_pending_bank`]
+    /// or [`Self::discard_pending_bank`] is called.
+    fn import_bank(&mut self, path: &Path) -> Result<(), Error> {
+        self.inner_mut().import_bank(path)
+    }
+
+    fn pending_bank(&self) -> &[Program] {
+        self.inner().pending_bank()
+    }
+
+    /// Prepares the user `Program`s to store so the bank matches `arrangement`.
+    ///
+    /// As with [`Self::import_bank`], the `Program`s are kept pending
+    /// until they are stored or discarded.
+    fn rearrange_bank(&mut self, arrangement: &Arrangement) {
+        self.inner_mut().rearrange_bank(arrangement);
+    }
+
+    /// Returns the progress of the bank dump in progress if any.
+    fn bank_dump_progress(&self) -> Option<BankDumpProgress> {
+        self.inner().bank_dump_progress()
+    }
+
+    /// Returns the progress of the `Program`s requested after the bank dump if any.
+    ///
+    /// See [`Requester::sync_programs`].
+    fn fetch_progress(&self) -> Option<FetchProgress> {
+        self.inner().fetch_progress()
+    }
+
+    /// Returns the `Program`s which don't need to be requested again
+    /// on next connection, i.e. the factory `Program`s received so far.
+    ///
+    /// See [`Requester::with_cached`].
+    fn cached_programs(&self) -> Vec<ProgramId> {
+        self.inner().cached_programs()
+    }
+
+    /// Returns whether the device edit buffer couldn't be related to a `Program`.
+    ///
+    /// This occurs if the edit buffer was modified before the connection
+    /// or if it matches several `Program`s.
+    fn is_origin_unknown(&self) -> bool {
+        self.inner().is_origin_unknown()
+    }
+
+    /// Returns whether the `Program` `prog_id` couldn't be read from the device.
+    fn is_unreadable(&self, prog_id: ProgramId) -> bool {
+        self.inner().is_unreadable(prog_id)
+    }
+
+    /// Returns whether the user `Program` slot `prog_id` is known to be empty.
+    fn is_empty_slot(&self, prog_id: ProgramId) -> bool {
+        self.inner().is_empty_slot(prog_id)
+    }
+
+    /// Flags the `Program`s `prog_ids` as unreadable, unless they were received.
+    ///
+    /// See [`Requester::sync_device`].
+    fn add_unreadable(&mut self, prog_ids: impl IntoIterator<Item = ProgramId>) {
+        self.inner_mut().add_unreadable(prog_ids);
+    }
+
+    /// Stores the pending `Program`s to the device.
+    ///
+    /// When no device is connected, the `Program`s are stored locally and
+    /// will be offered for sync when a device connects. Otherwise, returns
+    /// the `Program`s to store using [`Requester::store_programs`].
+    fn store_pending_bank(&mut self) -> Result<StoreBatch, Error> {
+        self.inner_mut().store_pending_bank()
+    }
+
+    /// Updates the `Program`s `progs` stored by the device.
+    ///
+    /// See [`Requester::store_programs`].
+    fn have_stored(&mut self, progs: Vec<Program>) {
+        self.inner_mut().have_stored(progs);
+    }
+
+    fn discard_pending_bank(&mut self) {
+        self.inner_mut().discard_pending_bank();
+    }
+
+    /// Returns a `Program` built from the edit buffer.
+    ///
+    /// The `Program` uses current `ProgramId` or the default one if unknown.
+    fn edit_buffer(&self) -> Program {
+        self.inner().edit_buffer()
+    }
+
+    /// Exports the edit buffer as a single `Program` to the `.syx` file at `path`.
+    fn export_program(&self, path: &Path) -> Result<(), Error> {
+        self.inner().export_program(path)
+    }
+
+    /// Imports the `Program` from the `.syx` file at `path` to the edit buffer.
+    ///
+    /// The `Program` is not stored, use [`Self::store_to`] for that.
+    fn import_program(&mut self, path: &Path) -> Result<(), Error> {
+        self.inner_mut().import_program(path)
+    }
+
+    /// Exports the edit buffer as a text preset to the file at `path`.
+    fn export_preset(&self, path: &Path) -> Result<(), Error> {
+        self.inner().export_preset(path)
+    }
+
+    /// Imports the text preset from the file at `path` to the edit buffer.
+    ///
+    /// The `Program` is not stored, use [`Self::store_to`] for that.
+    fn import_preset(&mut self, path: &Path) -> Result<(), Error> {
+        self.inner_mut().import_preset(path)
+    }
+
+    /// Sends the provided `ProgramData` to the device & local edit buffers.
+    ///
+    /// The `Program` is not stored, use [`Self::store_to`] for that.
+    fn send_to_edit_buffer(&mut self, data: &ProgramData) -> Result<(), Error> {
+        self.inner_mut().send_to_edit_buffer(data)
+    }
+
     fn update_param(&mut self, param: dsp::Parameter) {
         self.inner_mut().update_param(param);
     }
@@ -0,0 +1,145 @@
+use crate::jstation::{Program, ProgramId, ProgramNb};
+
+/// A rearrangement of the user bank, e.g. to reorder the Programs.
+///
+/// Each slot refers to the user `Program` it receives.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Arrangement {
+    slots: Vec<ProgramNb>,
+}
+
+impl Default for Arrangement {
+    /// Builds an `Arrangement` where each slot keeps its own `Program`.
+    fn default() -> Self {
+        Arrangement {
+            slots: Vec::from_iter(ProgramNb::enumerate()),
+        }
+    }
+}
+
+impl Arrangement {
+    /// Returns the slot whose `Program` is received by `nb`.
+    pub fn source(&self, nb: ProgramNb) -> ProgramNb {
+        self.slots[idx(nb)]
+    }
+
+    /// Moves the `Program` received by `from` to `to`, shifting the slots in between.
+    pub fn move_to(&mut self, from: ProgramNb, to: ProgramNb) {
+        let source = self.slots.remove(idx(from));
+        self.slots.insert(idx(to), source);
+    }
+
+    pub fn swap(&mut self, nb: ProgramNb, other: ProgramNb) {
+        self.slots.swap(idx(nb), idx(other));
+    }
+
+    /// Copies the `Program` received by `from` to `to`, replacing the latter.
+    pub fn duplicate(&mut self, from: ProgramNb, to: ProgramNb) {
+        self.slots[idx(to)] = self.slots[idx(from)];
+    }
+
+    pub fn is_modified(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Builds the `Program`s to store so the user bank matches the `Arrangement`.
+    ///
+    /// `get` returns the `Program`s currently stored on the device. Slots which
+    /// would receive an unknown `Program` or an identical one are skipped.
+    pub fn programs<'a>(&self, get: impl Fn(ProgramId) -> Option<&'a Program>) -> Vec<Program> {
+        ProgramNb::enumerate()
+            .filter_map(|nb| {
+                let source = get(ProgramId::new_user(self.source(nb)))?;
+                let target = ProgramId::new_user(nb);
+
+                let is_same = match get(target) {
+                    Some(cur) => {
+                        cur.data().buf() == source.data().buf()
+                            && cur.data().name() == source.data().name()
+                    }
+                    None => false,
+                };
+                if is_same {
+                    return None;
+                }
+
+                Some(Program::new(target, source.data().clone()))
+            })
+            .collect()
+    }
+}
+
+fn idx(nb: ProgramNb) -> usize {
+    u8::from(nb) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::Arrangement;
+    use crate::jstation::{Program, ProgramData, ProgramId, ProgramNb};
+
+    fn nb(nb: u8) -> ProgramNb {
+        ProgramNb::try_from(nb).unwrap()
+    }
+
+    #[test]
+    fn rearrange() {
+        let mut arrangement = Arrangement::default();
+        assert!(!arrangement.is_modified());
+
+        arrangement.move_to(nb(0), nb(2));
+        assert_eq!(arrangement.source(nb(0)), nb(1));
+        assert_eq!(arrangement.source(nb(1)), nb(2));
+        assert_eq!(arrangement.source(nb(2)), nb(0));
+        assert_eq!(arrangement.source(nb(3)), nb(3));
+
+        arrangement.swap(nb(0), nb(3));
+        assert_eq!(arrangement.source(nb(0)), nb(3));
+        assert_eq!(arrangement.source(nb(3)), nb(1));
+
+        arrangement.duplicate(nb(2), nb(4));
+        assert_eq!(arrangement.source(nb(4)), nb(0));
+        assert!(arrangement.is_modified());
+    }
+
+    #[test]
+    fn programs() {
+        let mut programs = BTreeMap::new();
+        for (idx, name) in ["A", "B", "A"].into_iter().enumerate() {
+            let prog_id = ProgramId::new_user(nb(idx as u8));
+            let mut data = ProgramData::default();
+            data.store_name(name);
+            programs.insert(prog_id, Program::new(prog_id, data));
+        }
+
+        let mut arrangement = Arrangement::default();
+        assert!(arrangement
+            .programs(|prog_id| programs.get(&prog_id))
+            .is_empty());
+
+        // Slot 2 already holds the same Program as slot 0.
+        arrangement.swap(nb(0), nb(2));
+        assert!(arrangement
+            .programs(|prog_id| programs.get(&prog_id))
+            .is_empty());
+
+        // Slot 3 is unknown: slot 1 keeps its Program.
+        arrangement.swap(nb(1), nb(3));
+        let progs = arrangement.programs(|prog_id| programs.get(&prog_id));
+        let [prog] = progs.as_slice() else {
+            panic!("Unexpected {progs:?}");
+        };
+        assert_eq!(prog.id(), ProgramId::new_user(nb(3)));
+        assert_eq!(prog.name(), "B");
+
+        arrangement.duplicate(nb(0), nb(3));
+        let progs = arrangement.programs(|prog_id| programs.get(&prog_id));
+        let [prog] = progs.as_slice() else {
+            panic!("Unexpected {progs:?}");
+        };
+        assert_eq!(prog.id(), ProgramId::new_user(nb(3)));
+        assert_eq!(prog.name(), "A");
+    }
+}
@@ -0,0 +1,307 @@
+use std::{fs, path::Path};
+
+use toml::{Table, Value};
+
+use crate::jstation::{
+    data::{dsp::Dsp, ProgramParameter, TextProgramParameter, TextValue},
+    Error, ProgramData,
+};
+
+const NAME: &str = "name";
+const AUTHOR: &str = "author";
+const TAGS: &str = "tags";
+const NOTES: &str = "notes";
+
+/// Optional information about a text preset.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Metadata {
+    pub author: String,
+    pub tags: Vec<String>,
+    pub notes: String,
+}
+
+/// Serializes the provided `ProgramData` as a text preset.
+///
+/// Each parameter is written by DSP block and field name, e.g.:
+///
+/// ```toml
+/// name = "My Preset"
+///
+/// [amp]
+/// modeling = "Brit Stack"
+/// gain = 60
+/// ```
+pub fn serialize(data: &ProgramData) -> Result<String, Error> {
+    serialize_with(data, &Metadata::default())
+}
+
+/// Serializes the provided `ProgramData` & `Metadata` as a text preset.
+///
+/// Empty `Metadata` fields are not written.
+pub fn serialize_with(data: &ProgramData, meta: &Metadata) -> Result<String, Error> {
+    let mut dsp = Dsp::default();
+    dsp.set_from(data)?;
+
+    let mut table = Table::new();
+    table.insert(NAME.to_string(), Value::String(dsp.name.clone()));
+    if !meta.author.is_empty() {
+        table.insert(AUTHOR.to_string(), Value::String(meta.author.clone()));
+    }
+    if !meta.tags.is_empty() {
+        let tags = meta.tags.iter().cloned().map(Value::String).collect();
+        table.insert(TAGS.to_string(), Value::Array(tags));
+    }
+    if !meta.notes.is_empty() {
+        table.insert(NOTES.to_string(), Value::String(meta.notes.clone()));
+    }
+
+    insert_group(&mut table, "compressor", &dsp.compressor);
+    insert_group(&mut table, "wah", &dsp.wah);
+    insert_group(&mut table, "amp", &dsp.amp);
+    insert_group(&mut table, "cabinet", &dsp.cabinet);
+    insert_group(&mut table, "noise_gate", &dsp.noise_gate);
+    insert_group(&mut table, "effect", &dsp.effect);
+    insert_group(&mut table, "delay", &dsp.delay);
+    insert_group(&mut table, "reverb", &dsp.reverb);
+    insert_group(&mut table, "expression", &dsp.expression);
+
+    Ok(table.to_string())
+}
+
+fn insert_group(table: &mut Table, name: &str, group: &impl TextProgramParameter) {
+    let group = Table::from_iter(group.to_text().into_iter().map(|(field, value)| {
+        let value = match value {
+            TextValue::Bool(value) => Value::Boolean(value),
+            TextValue::Int(value) => Value::Integer(value),
+            TextValue::Str(value) => Value::String(value),
+        };
+
+        (field.to_string(), value)
+    }));
+
+    table.insert(name.to_string(), Value::Table(group));
+}
+
+/// Parses the `ProgramData` from the provided text preset.
+///
+/// Parameters which are not defined in the preset use their default value.
+pub fn parse(input: &str) -> Result<ProgramData, Error> {
+    parse_with(input).map(|(data, _)| data)
+}
+
+/// Parses the `ProgramData` & `Metadata` from the provided text preset.
+pub fn parse_with(input: &str) -> Result<(ProgramData, Metadata), Error> {
+    let table: Table = input
+        .parse()
+        .map_err(|err| Error::with_context("Parsing preset", err))?;
+
+    let mut dsp = Dsp::default();
+    let mut meta = Metadata::default();
+    for (key, value) in table.iter() {
+        match (key.as_str(), value) {
+            (NAME, Value::String(name)) => dsp.name = name.clone(),
+            (AUTHOR, Value::String(author)) => meta.author = author.clone(),
+            (NOTES, Value::String(notes)) => meta.notes = notes.clone(),
+            (TAGS, Value::Array(tags)) => {
+                for tag in tags {
+                    let Value::String(tag) = tag else {
+                        return Err(Error::TextValueUnexpected {
+                            param: TAGS.to_string(),
+                            value: tag.to_string(),
+                        });
+                    };
+                    meta.tags.push(tag.clone());
+                }
+            }
+            ("compressor", Value::Table(group)) => set_group(&mut dsp.compressor, key, group)?,
+            ("wah", Value::Table(group)) => set_group(&mut dsp.wah, key, group)?,
+            ("amp", Value::Table(group)) => set_group(&mut dsp.amp, key, group)?,
+            ("cabinet", Value::Table(group)) => set_group(&mut dsp.cabinet, key, group)?,
+            ("noise_gate", Value::Table(group)) => set_group(&mut dsp.noise_gate, key, group)?,
+            ("effect", Value::Table(group)) => set_group(&mut dsp.effect, key, group)?,
+            ("delay", Value::Table(group)) => set_group(&mut dsp.delay, key, group)?,
+            ("reverb", Value::Table(group)) => set_group(&mut dsp.reverb, key, group)?,
+            ("expression", Value::Table(group)) => set_group(&mut dsp.expression, key, group)?,
+            _ => return Err(Error::PresetFieldUnknown(key.clone())),
+        }
+    }
+
+    let mut data = ProgramData::default();
+    dsp.store(&mut data);
+
+    Ok((data, meta))
+}
+
+fn set_group<G: TextProgramParameter>(
+    group: &mut G,
+    name: &str,
+    table: &Table,
+) -> Result<(), Error> {
+    let mut fields = Vec::with_capacity(table.len());
+    for (field, value) in table.iter() {
+        let param = format!("{name}.{field}");
+
+        if !G::TEXT_FIELDS.contains(&field.as_str()) {
+            return Err(Error::PresetFieldUnknown(param));
+        }
+
+        let value = match value {
+            Value::Boolean(value) => TextValue::Bool(*value),
+            Value::Integer(value) => TextValue::Int(*value),
+            Value::String(value) => TextValue::Str(value.clone()),
+            other => {
+                return Err(Error::TextValueUnexpected {
+                    param,
+                    value: other.to_string(),
+                })
+            }
+        };
+
+        fields.push((field.as_str(), value));
+    }
+
+    group
+        .set_from_text(|field| {
+            fields
+                .iter()
+                .find_map(|(name, value)| (*name == field).then_some(value))
+        })
+        .map_err(|err| Error::with_context(name.to_string(), err))
+}
+
+/// Sets the parameter at `path`, e.g. `amp.gain`, to `value` in the provided `Dsp`.
+pub fn set_param(dsp: &mut Dsp, path: &str, value: &TextValue) -> Result<(), Error> {
+    let (group, field) = path
+        .split_once('.')
+        .ok_or_else(|| Error::PresetFieldUnknown(path.to_string()))?;
+
+    match group {
+        "compressor" => set_field(&mut dsp.compressor, path, field, value),
+        "wah" => set_field(&mut dsp.wah, path, field, value),
+        "amp" => set_field(&mut dsp.amp, path, field, value),
+        "cabinet" => set_field(&mut dsp.cabinet, path, field, value),
+        "noise_gate" => set_field(&mut dsp.noise_gate, path, field, value),
+        "effect" => set_field(&mut dsp.effect, path, field, value),
+        "delay" => set_field(&mut dsp.delay, path, field, value),
+        "reverb" => set_field(&mut dsp.reverb, path, field, value),
+        "expression" => set_field(&mut dsp.expression, path, field, value),
+        _ => Err(Error::PresetFieldUnknown(path.to_string())),
+    }
+}
+
+fn set_field<G: TextProgramParameter>(
+    group: &mut G,
+    path: &str,
+    field: &str,
+    value: &TextValue,
+) -> Result<(), Error> {
+    if !G::TEXT_FIELDS.contains(&field) {
+        return Err(Error::PresetFieldUnknown(path.to_string()));
+    }
+
+    group
+        .set_from_text(|name| (name == field).then_some(value))
+        .map_err(|err| Error::with_context(path.to_string(), err))
+}
+
+/// Exports the provided `ProgramData` as a text preset to the file at `path`.
+pub fn export(path: &Path, data: &ProgramData) -> Result<(), Error> {
+    fs::write(path, serialize(data)?)
+        .map_err(|err| Error::with_context(format!("Exporting to {}", path.display()), err))
+}
+
+/// Imports the `ProgramData` from the text preset file at `path`.
+pub fn import(path: &Path) -> Result<ProgramData, Error> {
+    let input = fs::read_to_string(path)
+        .map_err(|err| Error::with_context(format!("Importing from {}", path.display()), err))?;
+
+    parse(&input)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::jstation::{
+        data::{dsp::Dsp, DiscreteParameter, ProgramParameter, TextValue},
+        ProgramData,
+    };
+
+    #[test]
+    fn round_trip() {
+        let mut data = ProgramData::default();
+        data.store_name("Text \"Preset\"");
+        // amp.modeling: Brit Stack
+        data.buf_mut()[9] = 5.into();
+        // delay.time_course
+        data.buf_mut()[29] = 3.into();
+        // effect.typ: Pitch / Detune & effect.speed
+        data.buf_mut()[20] = 6.into();
+        data.buf_mut()[22] = 48.into();
+
+        let text = super::serialize(&data).unwrap();
+        assert!(text.contains("modeling = \"Brit Stack\""));
+        assert!(text.contains("time_course = 3"));
+
+        let parsed = super::parse(&text).unwrap();
+        assert_eq!(parsed.name(), data.name());
+        assert_eq!(parsed.buf(), data.buf());
+    }
+
+    #[test]
+    fn metadata() {
+        let meta = super::Metadata {
+            author: "Me".to_string(),
+            tags: vec!["clean".to_string(), "Live".to_string()],
+            notes: "Neck pickup\nTone at 7".to_string(),
+        };
+
+        let text = super::serialize_with(&ProgramData::default(), &meta).unwrap();
+        let (_, parsed) = super::parse_with(&text).unwrap();
+        assert_eq!(parsed, meta);
+
+        // No metadata
+        let text = super::serialize(&ProgramData::default()).unwrap();
+        assert!(!text.contains("author"));
+        let (_, parsed) = super::parse_with(&text).unwrap();
+        assert_eq!(parsed, super::Metadata::default());
+    }
+
+    #[test]
+    fn partial() {
+        let parsed = super::parse("[delay]\ntime_course = 3").unwrap();
+
+        let mut dsp = Dsp::default();
+        dsp.set_from(&parsed).unwrap();
+        assert_eq!(dsp.delay.time_course.raw_value().as_u8(), 3);
+        assert_eq!(dsp.delay.time_fine, Default::default());
+    }
+
+    #[test]
+    fn set_param() {
+        let mut dsp = Dsp::default();
+        super::set_param(&mut dsp, "amp.gain", &TextValue::Int(60)).unwrap();
+        assert_eq!(dsp.amp.gain.raw_value().as_u8(), 60);
+
+        super::set_param(&mut dsp, "amp.modeling", &TextValue::from("Brit Stack")).unwrap();
+        assert_eq!(dsp.amp.modeling.raw_value().as_u8(), 5);
+
+        assert!(super::set_param(&mut dsp, "amp.gain", &TextValue::from("loud")).is_err());
+        assert!(super::set_param(&mut dsp, "amp.unknown", &TextValue::Int(60)).is_err());
+        assert!(super::set_param(&mut dsp, "gain", &TextValue::Int(60)).is_err());
+    }
+
+    #[test]
+    fn invalid() {
+        // Out of range
+        assert!(super::parse("[delay]\ntime_course = 31").is_err());
+        assert!(super::parse("[delay]\ntime_course = -1").is_err());
+        // Variable range out of range for the Effect type
+        assert!(super::parse("[effect]\ntyp = \"Auto Wah\"\nspeed = 3").is_err());
+        // Unknown names
+        assert!(super::parse("[amp]\nmodeling = \"Unknown\"").is_err());
+        assert!(super::parse("[amp]\nunknown = 3").is_err());
+        assert!(super::parse("[unknown]\nfield = 3").is_err());
+        // Wrong types
+        assert!(super::parse("[delay]\nswitch = 1").is_err());
+        assert!(super::parse("[delay]\nlevel = true").is_err());
+    }
+}
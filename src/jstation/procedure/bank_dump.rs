@@ -1,6 +1,6 @@
 use nom::IResult;
 
-use crate::jstation::{split_bytes, take_split_bytes_u16, BufferBuilder, ProcedureBuilder, ProcedureId};
+use crate::jstation::{take_split_bytes_u16, BufferBuilder, ProcedureBuilder, ProcedureId};
 
 #[derive(Debug)]
 pub struct BankDumpReq;
@@ -30,7 +30,8 @@ impl ProcedureId for StartBankDumpResp {
 
 impl ProcedureBuilder for StartBankDumpResp {
     fn push_variable_size_data(&self, buffer: &mut BufferBuilder) {
-        buffer.push_variable_size_data(split_bytes::from_u16(self.total_len).into_iter());
+        // `total_len` is sent as 2 bytes, LSB first, which are then split.
+        buffer.push_variable_size_data(self.total_len.to_le_bytes().into_iter());
     }
 }
 
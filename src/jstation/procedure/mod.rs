@@ -93,6 +93,7 @@ declare_procs!(
     program_indices: ProgramIndicesReq, ProgramIndicesResp;
     program_update: ProgramUpdateReq, ProgramUpdateResp;
     reload_program: ReloadProgramReq;
+    store_program: StoreProgramReq;
     who_am_i: WhoAmIReq, WhoAmIResp;
     result: ToMessageResp;
 );
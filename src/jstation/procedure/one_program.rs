@@ -2,7 +2,7 @@ use nom::{error::{self, Error}, IResult};
 
 use crate::jstation::{
     data::{Program, ProgramData, ProgramId},
-    take_split_bytes_u16, take_split_bytes_u8, take_u8, BufferBuilder, ProcedureBuilder, ProcedureId,
+    take_split_bytes_u16, take_split_bytes_u8, BufferBuilder, ProcedureBuilder, ProcedureId,
 };
 
 #[derive(Debug)]
@@ -26,8 +26,8 @@ impl ProcedureBuilder for OneProgramReq {
 
 impl OneProgramReq {
     pub fn parse<'i>(input: &'i [u8], checksum: &mut u8) -> IResult<&'i [u8], OneProgramReq> {
-        let (i, bank) = take_u8(input, checksum)?;
-        let (i, nb) = take_u8(i, checksum)?;
+        let (i, bank) = take_split_bytes_u8(input, checksum)?;
+        let (i, nb) = take_split_bytes_u8(i, checksum)?;
 
         let id = ProgramId::try_from_raw(bank, nb).map_err(|err| {
             log::error!("OneProgramReq: {err}");
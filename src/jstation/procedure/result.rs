@@ -62,6 +62,14 @@ pub enum Error {
 
 // FIXME these should be generated from a single definition
 
+impl Error {
+    /// Returns the id of the request procedure which caused the error.
+    pub fn req_proc(self) -> u8 {
+        let (req_proc, _) = self.into();
+        req_proc
+    }
+}
+
 impl From<Error> for (u8, u8) {
     fn from(err: Error) -> (u8, u8) {
         use Error::*;
@@ -0,0 +1,40 @@
+use crate::jstation::{
+    data::ProgramNb,
+    take_split_bytes_u8, BufferBuilder, ProcedureBuilder, ProcedureId,
+};
+
+/// Requests the device to store its edit buffer to the user Program `nb`.
+///
+/// The device acknowledges with a `ToMessageResp`.
+#[derive(Debug)]
+pub struct StoreProgramReq {
+    pub nb: ProgramNb,
+}
+
+impl ProcedureId for StoreProgramReq {
+    const ID: u8 = 0x21;
+    const VERSION: u8 = 1;
+}
+
+impl ProcedureBuilder for StoreProgramReq {
+    fn push_fixed_size_data(&self, buffer: &mut BufferBuilder) {
+        buffer.push_fixed_size_data(std::iter::once(self.nb.into()));
+    }
+}
+
+impl StoreProgramReq {
+    pub fn parse<'i>(input: &'i [u8], checksum: &mut u8) -> nom::IResult<&'i [u8], StoreProgramReq> {
+        let (i, nb) = take_split_bytes_u8(input, checksum)?;
+
+        let nb = ProgramNb::try_from(nb).map_err(|err| {
+            log::error!("StoreProgramReq: {err}");
+
+            nom::Err::Failure(nom::error::Error::new(
+                input,
+                nom::error::ErrorKind::TooLarge,
+            ))
+        })?;
+
+        Ok((i, StoreProgramReq { nb }))
+    }
+}
@@ -47,6 +47,18 @@ impl ProcedureId for WhoAmIResp {
     const VERSION: u8 = 1;
 }
 
+impl ProcedureBuilder for WhoAmIResp {
+    fn push_variable_size_data(&self, buffer: &mut BufferBuilder) {
+        let buf = [
+            self.receive_chan.as_u8(),
+            self.transmit_chan.as_u8(),
+            self.sysex_chan.as_u8(),
+        ];
+
+        buffer.push_variable_size_data(buf.into_iter());
+    }
+}
+
 impl WhoAmIResp {
     pub fn parse<'i>(input: &'i [u8], checksum: &mut u8) -> IResult<&'i [u8], WhoAmIResp> {
         let (i, _) = take_split_bytes_len(input, checksum, 3)?;
@@ -0,0 +1,571 @@
+use iced::futures::channel::{mpsc, oneshot};
+use std::{
+    collections::BTreeSet,
+    fmt,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::{
+    jstation::{
+        procedure, sysex, transport::Transport, Error, Procedure, ProcedureBuilder, ProcedureId,
+        Program, ProgramData, ProgramId, ProgramNb,
+    },
+    midi,
+};
+
+/// A `Transport` shared by the `Interface` & the `Requester`s.
+pub(super) type SharedTransport = Arc<Mutex<Box<dyn Transport>>>;
+
+/// An operation requested to the device.
+///
+/// Used to report the operation which failed when the device
+/// replies with an error acknowledgement or doesn't reply.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Operation {
+    Handshake,
+    UtilitySettingsRequest,
+    UtilitySettingsUpdate,
+    BankDump,
+    ProgramIndicesRequest,
+    /// The `ProgramId` is `None` if the request is unknown.
+    ProgramRequest(Option<ProgramId>),
+    EditBufferRequest,
+    EditBufferUpdate,
+    ProgramReload,
+    /// The `ProgramId` is `None` if the request is unknown.
+    ProgramStore(Option<ProgramId>),
+    Other {
+        req_proc: u8,
+    },
+}
+
+impl Operation {
+    /// Returns the `Operation` for the request procedure `req_proc`.
+    pub fn from_req_proc(req_proc: u8) -> Self {
+        match req_proc {
+            procedure::WhoAmIReq::ID => Operation::Handshake,
+            procedure::UtilitySettingsReq::ID => Operation::UtilitySettingsRequest,
+            procedure::UtilitySettingsResp::ID => Operation::UtilitySettingsUpdate,
+            procedure::BankDumpReq::ID => Operation::BankDump,
+            procedure::ProgramIndicesReq::ID => Operation::ProgramIndicesRequest,
+            procedure::OneProgramReq::ID => Operation::ProgramRequest(None),
+            procedure::OneProgramResp::ID | procedure::StoreProgramReq::ID => {
+                Operation::ProgramStore(None)
+            }
+            procedure::ProgramUpdateReq::ID => Operation::EditBufferRequest,
+            procedure::ProgramUpdateResp::ID => Operation::EditBufferUpdate,
+            procedure::ReloadProgramReq::ID => Operation::ProgramReload,
+            req_proc => Operation::Other { req_proc },
+        }
+    }
+
+    /// Returns whether the `Operation` can be sent again using [`Requester::retry`].
+    pub fn can_retry(self) -> bool {
+        matches!(
+            self,
+            Operation::UtilitySettingsRequest
+                | Operation::BankDump
+                | Operation::ProgramIndicesRequest
+                | Operation::ProgramRequest(Some(_))
+                | Operation::EditBufferRequest
+        )
+    }
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use Operation::*;
+        match self {
+            Handshake => f.write_str("Handshake"),
+            UtilitySettingsRequest => f.write_str("Utility Settings request"),
+            UtilitySettingsUpdate => f.write_str("Utility Settings update"),
+            BankDump => f.write_str("Bank dump"),
+            ProgramIndicesRequest => f.write_str("Program indices request"),
+            ProgramRequest(Some(prog_id)) => write!(f, "Program {prog_id} request"),
+            ProgramRequest(None) => f.write_str("Program request"),
+            EditBufferRequest => f.write_str("Edit buffer request"),
+            EditBufferUpdate => f.write_str("Edit buffer update"),
+            ProgramReload => f.write_str("Program reload"),
+            ProgramStore(Some(prog_id)) => write!(f, "Program {prog_id} store"),
+            ProgramStore(None) => f.write_str("Program store"),
+            Other { req_proc } => write!(f, "Procedure x{req_proc:02x}"),
+        }
+    }
+}
+
+/// Timeouts & retries for the device requests.
+#[derive(Clone, Copy, Debug)]
+pub struct RequestConfig {
+    /// Max duration to wait for a response.
+    pub timeout: Duration,
+    /// Max duration without any message during a bank dump.
+    pub bank_dump_timeout: Duration,
+    /// Number of times a request is sent again after a timeout.
+    pub retries: u8,
+}
+
+impl Default for RequestConfig {
+    fn default() -> Self {
+        RequestConfig {
+            timeout: Duration::from_secs(1),
+            bank_dump_timeout: Duration::from_secs(5),
+            retries: 2,
+        }
+    }
+}
+
+/// `Program`s to store to the device, see [`Requester::store_programs`].
+#[derive(Clone, Debug, Default)]
+pub struct StoreBatch {
+    pub progs: Vec<Program>,
+    /// The `Program` to change to once the `Program`s are stored.
+    pub change_to: Option<ProgramId>,
+    /// The edit buffer to restore once the `Program`s are stored.
+    pub edit_buffer: Option<ProgramData>,
+}
+
+impl StoreBatch {
+    pub fn is_empty(&self) -> bool {
+        self.progs.is_empty()
+    }
+}
+
+type Response = Result<Arc<sysex::Message>, procedure::result::Error>;
+
+/// A request awaiting its response.
+///
+/// Pending requests are registered with the `Listener`
+/// which resolves them as the device messages are received.
+pub(super) struct PendingRequest {
+    op: Operation,
+    req_proc: u8,
+    is_resp: Box<dyn Fn(&Procedure) -> bool + Send>,
+    resp_tx: oneshot::Sender<Response>,
+    /// How long a late response is awaited after the `Requester` gave up.
+    late_delay: Duration,
+    /// When the `Listener` found out the `Requester` gave up.
+    canceled_at: Option<Instant>,
+}
+
+impl PendingRequest {
+    /// Returns whether the `Requester` gave up on this request
+    /// long enough ago that no late response is expected.
+    pub(super) fn is_expired(&mut self, now: Instant) -> bool {
+        if !self.resp_tx.is_canceled() {
+            return false;
+        }
+
+        let canceled_at = *self.canceled_at.get_or_insert(now);
+        now.duration_since(canceled_at) >= self.late_delay
+    }
+
+    /// Tries to resolve this request with `sysex`.
+    ///
+    /// Returns `Err(self)` if `sysex` doesn't match.
+    ///
+    /// If the requester gave up in the meantime, e.g. after a timeout,
+    /// returns the `Error` for an error acknowledgement so it can still be reported.
+    pub(super) fn try_resolve(self, sysex: &Arc<sysex::Message>) -> Result<Option<Error>, Self> {
+        let resp = match &sysex.proc {
+            Procedure::ToMessageResp(procedure::ToMessageResp { res: Err(err) })
+                if err.req_proc() == self.req_proc =>
+            {
+                Err(*err)
+            }
+            proc if (self.is_resp)(proc) => Ok(sysex.clone()),
+            _ => return Err(self),
+        };
+
+        let op = self.op;
+        if let Err(Err(source)) = self.resp_tx.send(resp) {
+            return Ok(Some(Error::DeviceRequest { op, source }));
+        }
+
+        Ok(None)
+    }
+}
+
+/// Sends requests to the device & awaits their responses.
+///
+/// The responses are also received by the `Listener`, so the usual
+/// state updates are performed by `JStationImpl::handle_device`.
+/// Error acknowledgements for a request are only reported to its `Requester`,
+/// or by the `Listener` if the `Requester` gave up on it.
+///
+/// Get one using [`super::Interface::requester`].
+#[derive(Clone)]
+pub struct Requester {
+    transport: SharedTransport,
+    req_tx: mpsc::UnboundedSender<PendingRequest>,
+    cc_chan: midi::Channel,
+    sysex_chan: midi::Channel,
+    config: RequestConfig,
+    /// `Program`s already known, which are not requested by [`Self::sync_programs`].
+    cached: Arc<BTreeSet<ProgramId>>,
+}
+
+impl Requester {
+    pub(super) fn new(
+        transport: SharedTransport,
+        req_tx: mpsc::UnboundedSender<PendingRequest>,
+        cc_chan: midi::Channel,
+        sysex_chan: midi::Channel,
+        config: RequestConfig,
+    ) -> Self {
+        Requester {
+            transport,
+            req_tx,
+            cc_chan,
+            sysex_chan,
+            config,
+            cached: Arc::default(),
+        }
+    }
+
+    /// Skips the `Program`s `prog_ids` in [`Self::sync_programs`].
+    ///
+    /// E.g. the factory `Program`s received during a previous connection.
+    pub fn with_cached(mut self, prog_ids: impl IntoIterator<Item = ProgramId>) -> Self {
+        self.cached = Arc::new(BTreeSet::from_iter(prog_ids));
+        self
+    }
+
+    /// Sends `req` & awaits the first device message matching `is_resp`.
+    ///
+    /// The request is sent again if no response is received in time.
+    pub async fn request<R: ProcedureBuilder>(
+        &self,
+        op: Operation,
+        req: R,
+        timeout: Duration,
+        is_resp: impl Fn(&Procedure) -> bool + Send + 'static,
+    ) -> Result<Arc<sysex::Message>, Error> {
+        let msgs = [req.build_for(self.sysex_chan)];
+        self.send_request(op, R::ID, &msgs, timeout, || 0, is_resp)
+            .await
+    }
+
+    /// Sends `msgs` & awaits the first device message matching `is_resp`.
+    ///
+    /// `req_proc` is the request procedure the device acknowledges.
+    ///
+    /// Unlike [`Self::request`], `timeout` only applies to silences:
+    /// `msgs` are sent again if `progress` didn't change in time,
+    /// e.g. if no `Program`s were received during a bank dump.
+    async fn send_request(
+        &self,
+        op: Operation,
+        req_proc: u8,
+        msgs: &[Vec<u8>],
+        timeout: Duration,
+        progress: impl Fn() -> usize,
+        is_resp: impl Fn(&Procedure) -> bool + Send + 'static,
+    ) -> Result<Arc<sysex::Message>, Error> {
+        let (resp_tx, mut resp_rx) = oneshot::channel();
+        self.req_tx
+            .unbounded_send(PendingRequest {
+                op,
+                req_proc,
+                is_resp: Box::new(is_resp),
+                resp_tx,
+                late_delay: self.config.timeout,
+                canceled_at: None,
+            })
+            .map_err(|_| Error::MidiNotConnected)?;
+
+        for attempt in 0..=self.config.retries {
+            if attempt > 0 {
+                log::debug!("{op}: retrying ({attempt}/{})", self.config.retries);
+            }
+
+            {
+                // Don't interleave the messages with other requests.
+                let mut transport = self.transport.lock().expect("transport poisoned");
+                for msg in msgs {
+                    transport.send(msg)?;
+                }
+            }
+
+            let mut last_progress = progress();
+            let resp = loop {
+                let resp = smol::future::or(async { Some((&mut resp_rx).await) }, async {
+                    smol::Timer::after(timeout).await;
+                    None
+                })
+                .await;
+
+                if resp.is_some() {
+                    break resp;
+                }
+
+                let cur_progress = progress();
+                if cur_progress == last_progress {
+                    break None;
+                }
+
+                // Still receiving, e.g. a bank dump on a slow link.
+                last_progress = cur_progress;
+            };
+
+            match resp {
+                Some(Ok(Ok(sysex))) => return Ok(sysex),
+                Some(Ok(Err(source))) => return Err(Error::DeviceRequest { op, source }),
+                // The Listener was dropped
+                Some(Err(oneshot::Canceled)) => return Err(Error::MidiNotConnected),
+                None => (),
+            }
+        }
+
+        Err(Error::RequestTimeout(op))
+    }
+
+    pub async fn request_utility_settings(&self) -> Result<Arc<sysex::Message>, Error> {
+        self.request(
+            Operation::UtilitySettingsRequest,
+            procedure::UtilitySettingsReq,
+            self.config.timeout,
+            |proc| matches!(proc, Procedure::UtilitySettingsResp(_)),
+        )
+        .await
+    }
+
+    /// Requests the user `Program`s.
+    ///
+    /// Resolves with the `ProgramId`s received when the whole bank is received.
+    /// The request is only sent again if the device stops sending `Program`s.
+    pub async fn bank_dump(&self) -> Result<BTreeSet<ProgramId>, Error> {
+        let received = Arc::new(Mutex::new(BTreeSet::new()));
+        let progress = Arc::new(AtomicUsize::new(0));
+
+        self.send_request(
+            Operation::BankDump,
+            procedure::BankDumpReq::ID,
+            &[procedure::BankDumpReq.build_for(self.sysex_chan)],
+            self.config.bank_dump_timeout,
+            || progress.load(Ordering::Relaxed),
+            {
+                let received = received.clone();
+                let progress = progress.clone();
+                move |proc| match proc {
+                    Procedure::StartBankDumpResp(_) => {
+                        progress.fetch_add(1, Ordering::Relaxed);
+                        false
+                    }
+                    Procedure::OneProgramResp(resp) => {
+                        received.lock().unwrap().insert(resp.prog.id());
+                        progress.fetch_add(1, Ordering::Relaxed);
+                        false
+                    }
+                    Procedure::EndBankDumpResp(_) => true,
+                    _ => false,
+                }
+            },
+        )
+        .await?;
+
+        let received = std::mem::take(&mut *received.lock().unwrap());
+
+        Ok(received)
+    }
+
+    /// Requests the numbers of the populated user `Program`s.
+    pub async fn program_indices(&self) -> Result<BTreeSet<ProgramNb>, Error> {
+        let sysex = self
+            .request(
+                Operation::ProgramIndicesRequest,
+                procedure::ProgramIndicesReq,
+                self.config.timeout,
+                |proc| matches!(proc, Procedure::ProgramIndicesResp(_)),
+            )
+            .await?;
+
+        let Procedure::ProgramIndicesResp(resp) = &sysex.proc else {
+            unreachable!("matched above");
+        };
+
+        Ok(BTreeSet::from_iter(resp.numbers.iter().copied()))
+    }
+
+    pub async fn request_program(&self, id: ProgramId) -> Result<Arc<sysex::Message>, Error> {
+        self.request(
+            Operation::ProgramRequest(Some(id)),
+            procedure::OneProgramReq { id },
+            self.config.timeout,
+            move |proc| matches!(proc, Procedure::OneProgramResp(resp) if resp.prog.id() == id),
+        )
+        .await
+    }
+
+    /// Requests the device edit buffer.
+    pub async fn program_update_req(&self) -> Result<Arc<sysex::Message>, Error> {
+        self.request(
+            Operation::EditBufferRequest,
+            procedure::ProgramUpdateReq,
+            self.config.timeout,
+            |proc| matches!(proc, Procedure::ProgramUpdateResp(_)),
+        )
+        .await
+    }
+
+    /// Stores `prog` to the device through its edit buffer.
+    ///
+    /// Both the edit buffer update & the store request are sent again
+    /// if the device doesn't acknowledge the store in time.
+    pub async fn store_program(&self, prog: &Program) -> Result<(), Error> {
+        let nb = prog.id().nb();
+        let msgs = [
+            procedure::ProgramUpdateResp::from_changed(prog.data()).build_for(self.sysex_chan),
+            procedure::StoreProgramReq { nb }.build_for(self.sysex_chan),
+        ];
+
+        self.send_request(
+            Operation::ProgramStore(Some(prog.id())),
+            procedure::StoreProgramReq::ID,
+            &msgs,
+            self.config.timeout,
+            || 0,
+            |proc| {
+                matches!(
+                    proc,
+                    Procedure::ToMessageResp(procedure::ToMessageResp { res: Ok(req_proc) })
+                        if *req_proc == procedure::StoreProgramReq::ID
+                )
+            },
+        )
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stores the `Program`s of `batch` one by one, then restores the device state.
+    ///
+    /// Stops at the first `Program` the device fails to store.
+    ///
+    /// Returns the stored `Program`s, see `JStationImpl::have_stored`.
+    pub async fn store_programs(self, batch: StoreBatch) -> Result<Vec<Program>, Error> {
+        let mut res = Ok(());
+        for prog in batch.progs.iter() {
+            res = self.store_program(prog).await;
+            if res.is_err() {
+                break;
+            }
+        }
+
+        let mut restore = Vec::new();
+        if let Some(prog_id) = batch.change_to {
+            restore.push(midi::ProgramChange::build_for(prog_id.into(), self.cc_chan).to_vec());
+        }
+        if let Some(data) = batch.edit_buffer.as_ref() {
+            restore
+                .push(procedure::ProgramUpdateResp::from_changed(data).build_for(self.sysex_chan));
+        }
+        {
+            let mut transport = self.transport.lock().expect("transport poisoned");
+            for msg in restore {
+                transport.send(&msg)?;
+            }
+        }
+
+        res.map(|()| batch.progs)
+    }
+
+    /// Requests the `Program`s `prog_ids` one by one.
+    ///
+    /// Returns the `ProgramId`s which couldn't be read.
+    pub async fn request_programs(
+        &self,
+        prog_ids: impl IntoIterator<Item = ProgramId>,
+    ) -> Result<Vec<ProgramId>, Error> {
+        let mut unreadable = Vec::new();
+
+        for prog_id in prog_ids {
+            match self.request_program(prog_id).await {
+                Ok(_) => (),
+                Err(Error::MidiNotConnected) => return Err(Error::MidiNotConnected),
+                Err(err) => {
+                    log::warn!("{err}");
+                    unreadable.push(prog_id);
+                }
+            }
+        }
+
+        Ok(unreadable)
+    }
+
+    /// Requests the factory `Program`s & the populated user `Program`s.
+    ///
+    /// The user `Program`s missing from the bank dump are requested individually,
+    /// so are the factory `Program`s which are not cached. See [`Self::with_cached`].
+    ///
+    /// Returns the `ProgramId`s which couldn't be read.
+    pub async fn sync_programs(&self) -> Result<Vec<ProgramId>, Error> {
+        let populated = self.program_indices().await?;
+        let received = self.bank_dump().await?;
+
+        self.request_programs(ProgramId::enumerate().filter(|prog_id| {
+            !received.contains(prog_id)
+                && !self.cached.contains(prog_id)
+                && (prog_id.bank().is_factory() || populated.contains(&prog_id.nb()))
+        }))
+        .await
+    }
+
+    /// Requests the populated user `Program`s one by one.
+    ///
+    /// This is a faster alternative to [`Self::bank_dump`] when few
+    /// user `Program`s are populated.
+    ///
+    /// Returns the `ProgramId`s which couldn't be read.
+    pub async fn refresh_user_programs(self) -> Result<Vec<ProgramId>, Error> {
+        let populated = self.program_indices().await?;
+
+        self.request_programs(populated.into_iter().map(ProgramId::new_user))
+            .await
+    }
+
+    /// Synchronizes with the device after a handshake.
+    ///
+    /// Requests the Utility Settings, the `Program`s & the edit buffer.
+    /// See [`Self::sync_programs`].
+    ///
+    /// Returns the `ProgramId`s which couldn't be read.
+    pub async fn sync_device(self) -> Result<Vec<ProgramId>, Error> {
+        self.request_utility_settings().await?;
+        let unreadable = self.sync_programs().await?;
+        self.program_update_req().await?;
+
+        Ok(unreadable)
+    }
+
+    /// Sends the request for `op` again.
+    ///
+    /// Returns the `ProgramId`s which couldn't be read.
+    /// See [`Operation::can_retry`].
+    pub async fn retry(self, op: Operation) -> Result<Vec<ProgramId>, Error> {
+        match op {
+            Operation::UtilitySettingsRequest => {
+                self.request_utility_settings().await?;
+            }
+            Operation::ProgramIndicesRequest | Operation::BankDump => {
+                // Resume the sync.
+                let unreadable = self.sync_programs().await?;
+                self.program_update_req().await?;
+
+                return Ok(unreadable);
+            }
+            Operation::ProgramRequest(Some(prog_id)) => {
+                self.request_program(prog_id).await?;
+            }
+            Operation::EditBufferRequest => {
+                self.program_update_req().await?;
+            }
+            other => log::warn!("Can't retry {other}"),
+        }
+
+        Ok(Vec::new())
+    }
+}
@@ -0,0 +1,233 @@
+use std::path::PathBuf;
+
+use toml::{Table, Value};
+
+use crate::jstation::{
+    toml_file::{self, as_table},
+    Error, ProgramId, ProgramNb,
+};
+
+const FILE: &str = "setlists.toml";
+const SETLIST: &str = "setlist";
+const NAME: &str = "name";
+const SONG: &str = "song";
+const TITLE: &str = "title";
+const PROGRAM: &str = "program";
+const FACTORY: &str = "factory";
+const NOTES: &str = "notes";
+
+/// A song of a `Setlist`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Song {
+    pub title: String,
+    pub prog_id: ProgramId,
+    pub notes: String,
+}
+
+/// An ordered list of `Song`s, e.g. for a gig.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Setlist {
+    pub name: String,
+    pub songs: Vec<Song>,
+}
+
+/// The `Setlist`s, stored in a single text file.
+///
+/// ```toml
+/// [[setlist]]
+/// name = "Friday"
+///
+/// [[setlist.song]]
+/// title = "Intro"
+/// program = "1.2"
+/// notes = "Capo 2"
+/// ```
+///
+/// Factory `Program`s are flagged with `factory = true`.
+#[derive(Debug)]
+pub struct Setlists {
+    path: PathBuf,
+    pub lists: Vec<Setlist>,
+}
+
+impl Setlists {
+    /// Returns the default `Setlists` file for current user.
+    pub fn default_path() -> Option<PathBuf> {
+        toml_file::default_path(FILE)
+    }
+
+    /// Opens the `Setlists` at `path`, which is created when saved if needed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, Error> {
+        let path = path.into();
+
+        let lists = match toml_file::read(&path)? {
+            Some(input) => parse(&input)?,
+            None => Vec::new(),
+        };
+
+        Ok(Setlists { path, lists })
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        toml_file::write(&self.path, serialize(&self.lists))
+    }
+}
+
+/// Serializes the provided `Setlist`s as text.
+pub fn serialize(lists: &[Setlist]) -> String {
+    let lists = lists
+        .iter()
+        .map(|setlist| {
+            let songs = setlist
+                .songs
+                .iter()
+                .map(|song| {
+                    let mut table = Table::new();
+                    table.insert(TITLE.to_string(), Value::String(song.title.clone()));
+                    table.insert(
+                        PROGRAM.to_string(),
+                        Value::String(song.prog_id.nb().to_string()),
+                    );
+                    if song.prog_id.bank().is_factory() {
+                        table.insert(FACTORY.to_string(), Value::Boolean(true));
+                    }
+                    if !song.notes.is_empty() {
+                        table.insert(NOTES.to_string(), Value::String(song.notes.clone()));
+                    }
+
+                    Value::Table(table)
+                })
+                .collect();
+
+            let mut table = Table::new();
+            table.insert(NAME.to_string(), Value::String(setlist.name.clone()));
+            table.insert(SONG.to_string(), Value::Array(songs));
+
+            Value::Table(table)
+        })
+        .collect();
+
+    let mut table = Table::new();
+    table.insert(SETLIST.to_string(), Value::Array(lists));
+
+    table.to_string()
+}
+
+/// Parses the `Setlist`s from the provided text.
+pub fn parse(input: &str) -> Result<Vec<Setlist>, Error> {
+    let table: Table = input
+        .parse()
+        .map_err(|err| Error::with_context("Parsing setlists", err))?;
+
+    let mut lists = Vec::new();
+    for (key, value) in table.iter() {
+        match (key.as_str(), value) {
+            (SETLIST, Value::Array(values)) => {
+                for value in values {
+                    lists.push(parse_setlist(value)?);
+                }
+            }
+            _ => return Err(unknown(key.clone())),
+        }
+    }
+
+    Ok(lists)
+}
+
+fn parse_setlist(value: &Value) -> Result<Setlist, Error> {
+    let table = as_table(SETLIST, value)?;
+
+    let mut setlist = Setlist::default();
+    for (key, value) in table.iter() {
+        match (key.as_str(), value) {
+            (NAME, Value::String(name)) => setlist.name = name.clone(),
+            (SONG, Value::Array(values)) => {
+                for value in values {
+                    setlist.songs.push(parse_song(value)?);
+                }
+            }
+            _ => return Err(unknown(format!("{SETLIST}.{key}"))),
+        }
+    }
+
+    Ok(setlist)
+}
+
+fn parse_song(value: &Value) -> Result<Song, Error> {
+    let table = as_table(SONG, value)?;
+
+    let mut title = String::new();
+    let mut nb = None;
+    let mut is_factory = false;
+    let mut notes = String::new();
+    for (key, value) in table.iter() {
+        match (key.as_str(), value) {
+            (TITLE, Value::String(value)) => title = value.clone(),
+            (PROGRAM, Value::String(value)) => nb = Some(value.parse::<ProgramNb>()?),
+            (FACTORY, Value::Boolean(value)) => is_factory = *value,
+            (NOTES, Value::String(value)) => notes = value.clone(),
+            _ => return Err(unknown(format!("{SONG}.{key}"))),
+        }
+    }
+
+    let nb = nb.ok_or_else(|| Error::FieldMissing {
+        file: FILE,
+        field: format!("{SONG}.{PROGRAM}"),
+    })?;
+    let prog_id = if is_factory {
+        ProgramId::new_factory(nb)
+    } else {
+        ProgramId::new_user(nb)
+    };
+
+    Ok(Song {
+        title,
+        prog_id,
+        notes,
+    })
+}
+
+fn unknown(field: String) -> Error {
+    Error::FieldUnknown { file: FILE, field }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Setlist, Song};
+    use crate::jstation::{Error, ProgramId, ProgramNb};
+
+    #[test]
+    fn round_trip() {
+        let lists = vec![
+            Setlist {
+                name: "Friday".to_string(),
+                songs: vec![
+                    Song {
+                        title: "Intro".to_string(),
+                        prog_id: ProgramId::new_user("1.2".parse::<ProgramNb>().unwrap()),
+                        notes: "Capo 2".to_string(),
+                    },
+                    Song {
+                        title: "Encore".to_string(),
+                        prog_id: ProgramId::new_factory("9.3".parse::<ProgramNb>().unwrap()),
+                        notes: String::new(),
+                    },
+                ],
+            },
+            Setlist {
+                name: "Empty".to_string(),
+                songs: Vec::new(),
+            },
+        ];
+
+        let text = super::serialize(&lists);
+        assert!(text.contains("program = \"1.2\""));
+        assert!(text.contains("factory = true"));
+        assert_eq!(super::parse(&text).unwrap(), lists);
+
+        assert!(matches!(
+            super::parse("[[setlist]]\n[[setlist.song]]\ntitle = \"No program\""),
+            Err(Error::FieldMissing { .. }),
+        ));
+    }
+}
@@ -0,0 +1,1158 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use crate::jstation::prelude::*;
+use crate::{
+    jstation::{
+        channel_voice,
+        data::RawValue,
+        dsp, parse_raw_midi_msg, procedure,
+        transport::{self, Incoming, Loopback},
+        Message, Procedure, ProcedureBuilder, ProcedureId, Program, ProgramData, ProgramId,
+        ProgramNb, ProgramsBank, Transport,
+    },
+    midi,
+};
+
+/// A virtual J-Station.
+///
+/// The `Simulator` answers the requests sent by the application the way
+/// the device does, which allows testing without the actual hardware.
+pub struct Simulator {
+    utility_settings: procedure::UtilitySettingsResp,
+    programs: BTreeMap<ProgramId, Program>,
+    cur_prog_id: ProgramId,
+    dsp: dsp::Dsp,
+    has_changed: bool,
+}
+
+impl Default for Simulator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Simulator {
+    /// The MIDI channel used by default, i.e. channel 1.
+    const DEFAULT_CHAN: u8 = 0;
+
+    /// Builds a `Simulator` with distinct user & factory `Program`s.
+    pub fn new() -> Self {
+        let mut programs = BTreeMap::new();
+        for bank in [ProgramsBank::User, ProgramsBank::Factory] {
+            for nb in ProgramNb::enumerate() {
+                let mut data = ProgramData::default();
+                data.store_name(&format!("{bank} {nb}"));
+                // amp.modeling
+                let offset = if bank.is_user() { 0 } else { 12 };
+                data.buf_mut()[9] = RawValue::new((u8::from(nb) + offset) % 25);
+
+                let prog_id = bank.into_prog_id(nb);
+                programs.insert(prog_id, Program::new(prog_id, data));
+            }
+        }
+
+        let cur_prog_id = ProgramId::default();
+        let mut dsp = dsp::Dsp::default();
+        dsp.set_from(programs[&cur_prog_id].data())
+            .expect("valid default program");
+
+        Simulator {
+            utility_settings: procedure::UtilitySettingsResp {
+                midi_channel: midi::Channel::from(Self::DEFAULT_CHAN),
+                ..Default::default()
+            },
+            programs,
+            cur_prog_id,
+            dsp,
+            has_changed: false,
+        }
+    }
+
+    /// Returns the channel the device currently uses.
+    pub fn chan(&self) -> midi::Channel {
+        self.utility_settings.midi_channel
+    }
+
+    pub fn dsp(&self) -> &dsp::Dsp {
+        &self.dsp
+    }
+
+    pub fn cur_prog_id(&self) -> ProgramId {
+        self.cur_prog_id
+    }
+
+    pub fn get_program(&self, prog_id: ProgramId) -> Option<&Program> {
+        self.programs.get(&prog_id)
+    }
+
+    /// Handles the provided raw MIDI message.
+    ///
+    /// Returns the raw MIDI messages the device sends in response.
+    pub fn handle(&mut self, msg: &[u8]) -> Vec<Vec<u8>> {
+        let msg = match parse_raw_midi_msg(msg) {
+            Ok((_, msg)) => msg,
+            Err(err) => {
+                log::warn!("Simulator: failed to parse message: {err}");
+                return Vec::new();
+            }
+        };
+
+        match msg {
+            Message::SysEx(sysex) => {
+                if sysex.chan != self.chan() && sysex.chan != midi::Channel::ALL {
+                    log::trace!("Simulator: ignoring sysex on {}", sysex.chan);
+                    return Vec::new();
+                }
+
+                self.handle_proc(Arc::try_unwrap(sysex).unwrap().proc)
+            }
+            Message::ChannelVoice(cv) => {
+                if cv.chan != self.chan() {
+                    log::trace!("Simulator: ignoring channel voice on {}", cv.chan);
+                    return Vec::new();
+                }
+
+                self.handle_channel_voice(cv.msg);
+
+                Vec::new()
+            }
+        }
+    }
+
+    fn handle_proc(&mut self, proc: Procedure) -> Vec<Vec<u8>> {
+        use Procedure::*;
+
+        let chan = self.chan();
+        match proc {
+            WhoAmIReq(_) => vec![procedure::WhoAmIResp {
+                receive_chan: chan,
+                transmit_chan: chan,
+                sysex_chan: chan,
+            }
+            .build_for(chan)],
+            UtilitySettingsReq(_) => vec![self.utility_settings.build_for(chan)],
+            UtilitySettingsResp(resp) => {
+                // The device uses the new channel from now on.
+                self.utility_settings = resp;
+
+                Vec::new()
+            }
+            BankDumpReq(_) => {
+                let progs = Vec::from_iter(
+                    self.programs
+                        .values()
+                        .filter(|prog| prog.id().bank().is_user())
+                        .map(|prog| procedure::OneProgramResp::from(prog).build_for(chan)),
+                );
+
+                let total_len = progs
+                    .iter()
+                    .map(Vec::len)
+                    .sum::<usize>()
+                    .try_into()
+                    .expect("bank dump length overflow");
+
+                let mut msgs = vec![procedure::StartBankDumpResp { total_len }.build_for(chan)];
+                msgs.extend(progs);
+                msgs.push(procedure::EndBankDumpResp.build_for(chan));
+
+                msgs
+            }
+            OneProgramReq(req) => match self.programs.get(&req.id) {
+                Some(prog) => vec![procedure::OneProgramResp::from(prog).build_for(chan)],
+                None => vec![self.error_resp(procedure::result::Error::ProgramNumber {
+                    req_proc: procedure::OneProgramReq::ID,
+                })],
+            },
+            OneProgramResp(resp) => {
+                let prog_id = resp.prog.id();
+                if !prog_id.bank().is_user() {
+                    return vec![
+                        self.error_resp(procedure::result::Error::UserProgramNumber {
+                            req_proc: procedure::OneProgramResp::ID,
+                        }),
+                    ];
+                }
+
+                if prog_id == self.cur_prog_id {
+                    self.has_changed = self.dsp.has_changed(resp.prog.data());
+                }
+                self.programs.insert(prog_id, resp.prog);
+
+                vec![procedure::ToMessageResp {
+                    res: Ok(procedure::OneProgramResp::ID),
+                }
+                .build_for(chan)]
+            }
+            ProgramIndicesReq(_) => {
+                let mut resp = procedure::ProgramIndicesResp::default();
+                resp.numbers.extend(
+                    self.programs
+                        .keys()
+                        .filter(|prog_id| prog_id.bank().is_user())
+                        .map(|prog_id| prog_id.nb()),
+                );
+
+                vec![resp.build_for(chan)]
+            }
+            ProgramUpdateReq(_) => {
+                let mut prog_data = self.programs[&self.cur_prog_id].data().clone();
+                self.dsp.store(&mut prog_data);
+
+                vec![procedure::program_update::ProgramUpdateRefResp {
+                    has_changed: self.has_changed,
+                    prog_data: &prog_data,
+                }
+                .build_for(chan)]
+            }
+            ProgramUpdateResp(resp) => {
+                if let Err(err) = self.dsp.set_from(&resp.prog_data) {
+                    log::warn!("Simulator: {err}");
+                    return Vec::new();
+                }
+                self.has_changed = true;
+
+                Vec::new()
+            }
+            ReloadProgramReq(_) => {
+                self.load_prog(self.cur_prog_id);
+
+                Vec::new()
+            }
+            StoreProgramReq(req) => {
+                let prog_id = ProgramId::new_user(req.nb);
+                let mut data = self
+                    .programs
+                    .get(&self.cur_prog_id)
+                    .map_or_else(ProgramData::default, |prog| prog.data().clone());
+                self.dsp.store(&mut data);
+
+                self.programs.insert(prog_id, Program::new(prog_id, data));
+                self.cur_prog_id = prog_id;
+                self.has_changed = false;
+
+                vec![procedure::ToMessageResp {
+                    res: Ok(procedure::StoreProgramReq::ID),
+                }
+                .build_for(chan)]
+            }
+            other => {
+                log::debug!("Simulator: ignoring {other:?}");
+
+                Vec::new()
+            }
+        }
+    }
+
+    fn handle_channel_voice(&mut self, msg: channel_voice::Message) {
+        use channel_voice::Message::*;
+        match msg {
+            CC(cc) => match self.dsp.set_cc(cc) {
+                Ok(Some(_)) => self.has_changed = true,
+                Ok(None) => (),
+                Err(err) => log::debug!("Simulator: {err}"),
+            },
+            ProgramChange(prog_id) => self.load_prog(prog_id),
+        }
+    }
+
+    fn load_prog(&mut self, prog_id: ProgramId) {
+        let Some(prog) = self.programs.get(&prog_id) else {
+            log::warn!("Simulator: unknown Program {prog_id}");
+            return;
+        };
+
+        self.dsp.set_from(prog.data()).expect("valid program");
+        self.cur_prog_id = prog_id;
+        self.has_changed = false;
+    }
+
+    fn error_resp(&self, err: procedure::result::Error) -> Vec<u8> {
+        procedure::ToMessageResp { res: Err(err) }.build_for(self.chan())
+    }
+
+    /// Runs the `Simulator` on a dedicated thread.
+    ///
+    /// Returns the application end of an in-memory connection
+    /// to use with [`crate::jstation::Interface::connect_transport`].
+    pub fn spawn(mut self) -> (Loopback, Incoming) {
+        let (host, (mut device_tx, mut device_rx)) = transport::loopback();
+
+        std::thread::spawn(move || {
+            smol::block_on(async move {
+                while let Some(msg) = device_rx.recv().await {
+                    for resp in self.handle(&msg) {
+                        if device_tx.send(&resp).is_err() {
+                            // Disconnected
+                            return;
+                        }
+                    }
+                }
+            })
+        });
+
+        host
+    }
+}
+
+/// A `Simulator` exposed as virtual MIDI ports.
+///
+/// The ports are available as long as this is alive.
+#[cfg(unix)]
+pub struct VirtualPorts {
+    _midi_in: midir::MidiInputConnection<(Simulator, midir::MidiOutputConnection)>,
+}
+
+#[cfg(unix)]
+impl Simulator {
+    pub const VIRTUAL_PORT_NAME: &'static str = "J-Station Simulator";
+
+    /// Exposes the `Simulator` as a pair of virtual MIDI ports.
+    ///
+    /// The application can then connect to the ports as it would for the device.
+    pub fn run_virtual(self) -> Result<VirtualPorts, midi::Error> {
+        use midir::os::unix::{VirtualInput, VirtualOutput};
+
+        let midi_out = midir::MidiOutput::new(Self::VIRTUAL_PORT_NAME)?
+            .create_virtual(Self::VIRTUAL_PORT_NAME)
+            .map_err(|_| midi::Error::PortCreation)?;
+
+        let midi_in = midir::MidiInput::new(Self::VIRTUAL_PORT_NAME)?
+            .create_virtual(
+                Self::VIRTUAL_PORT_NAME,
+                |_ts, msg, (sim, midi_out)| {
+                    for resp in sim.handle(msg) {
+                        if let Err(err) = midi_out.send(&resp) {
+                            log::error!("Simulator: {err}");
+                        }
+                    }
+                },
+                (self, midi_out),
+            )
+            .map_err(|_| midi::Error::PortCreation)?;
+
+        log::info!("Running {} on virtual ports", Self::VIRTUAL_PORT_NAME);
+
+        Ok(VirtualPorts { _midi_in: midi_in })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::futures::FutureExt;
+    use std::{future::Future, sync::Arc, time::Duration};
+
+    use super::Simulator;
+    use crate::{
+        jstation::{
+            dsp, parse_raw_midi_msg,
+            prelude::*,
+            procedure,
+            transport::{self, Incoming, Loopback},
+            Arrangement, CCParameter, Error, JStation, Listener, Message, Operation, Procedure,
+            ProcedureBuilder, ProcedureId, Program, ProgramData, ProgramId, ProgramNb,
+            RequestConfig, StoreBatch, Transport,
+        },
+        midi,
+    };
+
+    fn parse_procs(msgs: Vec<Vec<u8>>) -> Vec<Procedure> {
+        Vec::from_iter(msgs.iter().map(|msg| {
+            let (_, msg) = parse_raw_midi_msg(msg).unwrap();
+            let Message::SysEx(sysex) = msg else {
+                panic!("Unexpected {msg:?}");
+            };
+
+            Arc::try_unwrap(sysex).unwrap().proc
+        }))
+    }
+
+    fn listen(listener: &mut Listener) -> Message {
+        smol::block_on(smol::future::or(
+            async { Some(listener.listen().await.unwrap()) },
+            async {
+                smol::Timer::after(Duration::from_secs(2)).await;
+                None
+            },
+        ))
+        .expect("Timeout waiting for device message")
+    }
+
+    /// Runs `req` to completion, handling device messages meanwhile.
+    fn run<T>(
+        jstation: &mut JStation,
+        listener: &mut Listener,
+        req: impl Future<Output = Result<T, Error>>,
+    ) -> Result<T, Error> {
+        smol::block_on(async {
+            smol::pin!(req);
+            loop {
+                let msg = smol::future::or(async { Err(req.as_mut().await) }, async {
+                    Ok(listener.listen().await.unwrap())
+                })
+                .await;
+
+                match msg {
+                    Ok(msg) => jstation.handle_device(msg).unwrap(),
+                    Err(res) => return res,
+                }
+            }
+        })
+    }
+
+    /// Returns the edit buffer of the device.
+    fn device_edit_buffer(jstation: &mut JStation, listener: &mut Listener) -> ProgramData {
+        let requester = jstation.iface().requester().unwrap();
+        let sysex = run(jstation, listener, requester.program_update_req()).unwrap();
+        let Procedure::ProgramUpdateResp(resp) = &sysex.proc else {
+            panic!("Unexpected {:?}", sysex.proc);
+        };
+
+        resp.prog_data.clone()
+    }
+
+    /// Stores the `Program`s returned by `f` to the device.
+    fn store(
+        jstation: &mut JStation,
+        listener: &mut Listener,
+        f: impl FnOnce(&mut JStation) -> Result<StoreBatch, Error>,
+    ) -> Result<(), Error> {
+        let batch = f(jstation)?;
+        let requester = jstation.iface().requester().unwrap();
+        let progs = run(jstation, listener, requester.store_programs(batch))?;
+        jstation.have_stored(progs);
+
+        Ok(())
+    }
+
+    /// Connects `jstation` to the device end of a `loopback` after a handshake.
+    fn connect_loopback(jstation: &mut JStation) -> (Listener, Loopback, Incoming) {
+        let ((host, host_in), (mut device, device_in)) = transport::loopback();
+        let mut listener = jstation
+            .iface_mut()
+            .connect_transport(host, host_in)
+            .unwrap();
+
+        let chan = midi::Channel::from(0);
+        device
+            .send(
+                &procedure::WhoAmIResp {
+                    receive_chan: chan,
+                    transmit_chan: chan,
+                    sysex_chan: chan,
+                }
+                .build_for(chan),
+            )
+            .unwrap();
+        let msg = listen(&mut listener);
+        jstation.handle_device(msg).unwrap();
+
+        (listener, device, device_in)
+    }
+
+    /// Connects to a `Simulator` and synchronizes with it.
+    fn connect(sim: Simulator) -> (JStation, Listener) {
+        let mut jstation = JStation::new("test".into());
+        let listener = connect_with(&mut jstation, sim);
+
+        (jstation, listener)
+    }
+
+    /// Connects `jstation` to a `Simulator` and synchronizes with it.
+    fn connect_with(jstation: &mut JStation, sim: Simulator) -> Listener {
+        let (transport, incoming) = sim.spawn();
+        let mut listener = jstation
+            .iface_mut()
+            .connect_transport(transport, incoming)
+            .unwrap();
+
+        let msg = listen(&mut listener);
+        jstation.handle_device(msg).unwrap();
+
+        let requester = jstation.iface().requester().unwrap();
+        run(jstation, &mut listener, requester.sync_device()).unwrap();
+
+        listener
+    }
+
+    #[test]
+    fn requests() {
+        let mut sim = Simulator::new();
+        let chan = sim.chan();
+
+        let procs =
+            parse_procs(sim.handle(&procedure::WhoAmIReq::default().build_for(midi::Channel::ALL)));
+        let [Procedure::WhoAmIResp(resp)] = procs.as_slice() else {
+            panic!("Unexpected {procs:?}");
+        };
+        assert_eq!(resp.sysex_chan, chan);
+
+        let procs = parse_procs(sim.handle(&procedure::BankDumpReq.build_for(chan)));
+        assert_eq!(procs.len(), 32);
+        assert!(matches!(procs[0], Procedure::StartBankDumpResp(_)));
+        assert!(matches!(procs[31], Procedure::EndBankDumpResp(_)));
+
+        let id = ProgramId::new_factory(ProgramNb::try_from(4).unwrap());
+        let procs = parse_procs(sim.handle(&procedure::OneProgramReq { id }.build_for(chan)));
+        let [Procedure::OneProgramResp(resp)] = procs.as_slice() else {
+            panic!("Unexpected {procs:?}");
+        };
+        assert_eq!(resp.prog.id(), id);
+        assert_eq!(resp.prog.name(), sim.get_program(id).unwrap().name());
+
+        // Ignored on another channel
+        assert!(sim
+            .handle(&procedure::UtilitySettingsReq.build_for(midi::Channel::from(5)))
+            .is_empty());
+    }
+
+    #[test]
+    fn missing_programs() {
+        let empty_slot = ProgramId::new_user(ProgramNb::try_from(7).unwrap());
+        let missing_factory = ProgramId::new_factory(ProgramNb::try_from(2).unwrap());
+
+        let mut sim = Simulator::new();
+        sim.programs.remove(&empty_slot);
+        sim.programs.remove(&missing_factory);
+
+        let mut jstation = JStation::new("test".into());
+        let (transport, incoming) = sim.spawn();
+        let mut listener = jstation
+            .iface_mut()
+            .connect_transport(transport, incoming)
+            .unwrap();
+
+        let msg = listen(&mut listener);
+        jstation.handle_device(msg).unwrap();
+
+        let requester = jstation.iface().requester().unwrap();
+        let unreadable = run(&mut jstation, &mut listener, requester.sync_device()).unwrap();
+        // Empty slots are not requested.
+        assert_eq!(unreadable, vec![missing_factory]);
+
+        jstation.add_unreadable(unreadable);
+        for prog_id in ProgramId::enumerate() {
+            let is_missing = prog_id == empty_slot || prog_id == missing_factory;
+            assert_eq!(jstation.get_program(prog_id).is_none(), is_missing);
+            assert_eq!(jstation.is_unreadable(prog_id), prog_id == missing_factory);
+            assert_eq!(jstation.is_empty_slot(prog_id), prog_id == empty_slot);
+        }
+
+        // Incremental refresh
+        let requester = jstation.iface().requester().unwrap();
+        let unreadable = run(
+            &mut jstation,
+            &mut listener,
+            requester.refresh_user_programs(),
+        )
+        .unwrap();
+        assert!(unreadable.is_empty());
+        assert!(jstation.is_empty_slot(empty_slot));
+
+        // Storing to the empty slot
+        jstation.change_program(empty_slot).unwrap();
+        jstation.rename("Stored");
+        store(&mut jstation, &mut listener, |jstation| {
+            jstation.store_to(empty_slot.nb())
+        })
+        .unwrap();
+        assert_eq!(jstation.get_program(empty_slot).unwrap().name(), "Stored");
+        assert!(!jstation.is_empty_slot(empty_slot));
+    }
+
+    #[test]
+    fn bank_dump_progress() {
+        let mut sim = Simulator::new();
+        let msgs = sim.handle(&procedure::BankDumpReq.build_for(sim.chan()));
+        let prog_count = msgs.len() - 2;
+
+        let mut jstation = JStation::new("test".into());
+        for (idx, msg) in msgs.iter().enumerate() {
+            let (_, msg) = parse_raw_midi_msg(msg).unwrap();
+            jstation.handle_device(msg).unwrap();
+
+            if idx <= prog_count {
+                let progress = jstation.bank_dump_progress().unwrap();
+                assert_eq!(progress.received, idx);
+                assert_eq!(progress.expected(), prog_count);
+                if idx == prog_count {
+                    assert_eq!(progress.ratio(), 1.0);
+                }
+            }
+        }
+
+        assert!(jstation.bank_dump_progress().is_none());
+    }
+
+    #[test]
+    fn fetch_progress() {
+        fn handle(jstation: &mut JStation, sim: &mut Simulator, req: impl ProcedureBuilder) {
+            for msg in sim.handle(&req.build_for(sim.chan())) {
+                let (_, msg) = parse_raw_midi_msg(&msg).unwrap();
+                jstation.handle_device(msg).unwrap();
+            }
+        }
+
+        let factory_count = ProgramNb::enumerate().count();
+        let factory_1 = ProgramId::new_factory(ProgramNb::try_from(0).unwrap());
+
+        let mut sim = Simulator::new();
+        let mut jstation = JStation::new("test".into());
+        handle(&mut jstation, &mut sim, procedure::ProgramIndicesReq);
+        handle(&mut jstation, &mut sim, procedure::BankDumpReq);
+        let progress = jstation.fetch_progress().unwrap();
+        assert_eq!(progress.received, 0);
+        assert_eq!(progress.expected, factory_count);
+
+        handle(
+            &mut jstation,
+            &mut sim,
+            procedure::OneProgramReq { id: factory_1 },
+        );
+        assert_eq!(jstation.fetch_progress().unwrap().received, 1);
+
+        // Sync done
+        jstation.add_unreadable([]);
+        assert!(jstation.fetch_progress().is_none());
+
+        // The factory `Program`s are kept on next connection.
+        handle(&mut jstation, &mut sim, procedure::WhoAmIReq::default());
+        assert_eq!(jstation.cached_programs(), vec![factory_1]);
+        assert!(jstation.get_program(factory_1).is_some());
+
+        handle(&mut jstation, &mut sim, procedure::BankDumpReq);
+        assert_eq!(
+            jstation.fetch_progress().unwrap().expected,
+            factory_count - 1
+        );
+    }
+
+    #[test]
+    fn cc() {
+        let mut sim = Simulator::new();
+
+        let gain = dsp::amp::Gain::try_from_raw(60.into()).unwrap();
+        let cc = dsp::amp::Parameter::from(gain).to_cc().unwrap();
+        sim.handle(&cc.build_for(sim.chan()));
+        assert_eq!(sim.dsp().amp.gain, gain);
+
+        let procs = parse_procs(sim.handle(&procedure::ProgramUpdateReq.build_for(sim.chan())));
+        let [Procedure::ProgramUpdateResp(resp)] = procs.as_slice() else {
+            panic!("Unexpected {procs:?}");
+        };
+        assert!(resp.has_changed);
+
+        // Reload
+        sim.handle(&procedure::ReloadProgramReq.build_for(sim.chan()));
+        assert_ne!(sim.dsp().amp.gain, gain);
+    }
+
+    #[test]
+    fn handshake_and_bank_dump() {
+        let sim = Simulator::new();
+        let user_2 = ProgramId::new_user(ProgramNb::try_from(1).unwrap());
+        let user_2_name = sim.get_program(user_2).unwrap().name().to_string();
+
+        // Handshake, utility settings, bank dump & program update
+        let (mut jstation, mut listener) = connect(sim);
+
+        assert_eq!(jstation.cur_prog_id(), Some(ProgramId::default()));
+        assert!(!jstation.has_changed());
+        for nb in ProgramNb::enumerate() {
+            assert!(jstation.get_program(ProgramId::new_user(nb)).is_some());
+        }
+
+        // Change program & edit a parameter
+        jstation.change_program(user_2).unwrap();
+        assert_eq!(jstation.dsp().name, user_2_name);
+
+        let gain = dsp::amp::Gain::try_from_raw(60.into()).unwrap();
+        jstation.update_param(dsp::amp::Parameter::from(gain).into());
+        assert!(jstation.has_changed());
+
+        let requester = jstation.iface().requester().unwrap();
+        let sysex = run(&mut jstation, &mut listener, requester.program_update_req()).unwrap();
+        let Procedure::ProgramUpdateResp(resp) = &sysex.proc else {
+            panic!("Unexpected {:?}", sysex.proc);
+        };
+        assert!(resp.has_changed);
+        assert_eq!(resp.prog_data.name(), user_2_name);
+        assert_eq!(resp.prog_data.buf(), jstation.edit_buffer().data().buf());
+
+        // Identified from the last Program change
+        assert_eq!(jstation.cur_prog_id(), Some(user_2));
+        assert!(jstation.has_changed());
+    }
+
+    #[test]
+    fn undo_redo() {
+        let (mut jstation, mut listener) = connect(Simulator::new());
+        let stored = jstation.edit_buffer().data().clone();
+
+        let gain = dsp::amp::Gain::try_from_raw(60.into()).unwrap();
+        jstation.update_param(dsp::amp::Parameter::from(gain).into());
+        jstation.rename("Edited");
+        assert!(jstation.has_changed());
+
+        jstation.undo().unwrap();
+        assert_eq!(jstation.dsp().name, stored.name());
+        jstation.undo().unwrap();
+        assert!(!jstation.has_changed());
+        assert!(!jstation.can_undo());
+
+        // The undone parameter was sent to the device.
+        assert_eq!(
+            device_edit_buffer(&mut jstation, &mut listener).buf(),
+            stored.buf()
+        );
+
+        jstation.redo().unwrap();
+        assert_eq!(jstation.dsp().amp.gain, gain);
+        assert!(jstation.can_redo());
+        assert!(jstation.has_changed());
+
+        // Changing Program clears the history.
+        jstation
+            .change_program(ProgramId::new_user(ProgramNb::try_from(1).unwrap()))
+            .unwrap();
+        assert!(!jstation.can_undo());
+        assert!(!jstation.can_redo());
+    }
+
+    #[test]
+    fn compare() {
+        let (mut jstation, mut listener) = connect(Simulator::new());
+        let stored = jstation.edit_buffer().data().clone();
+
+        let gain = dsp::amp::Gain::try_from_raw(60.into()).unwrap();
+        jstation.update_param(dsp::amp::Parameter::from(gain).into());
+        let edited = jstation.edit_buffer().data().clone();
+
+        // Play the stored version
+        jstation.toggle_compare().unwrap();
+        assert!(jstation.is_comparing());
+        assert!(jstation.has_changed());
+        assert_ne!(jstation.dsp().amp.gain, gain);
+        assert_eq!(jstation.edit_buffer().data().buf(), edited.buf());
+        assert_eq!(
+            device_edit_buffer(&mut jstation, &mut listener).buf(),
+            stored.buf()
+        );
+
+        // Back to the edited version
+        jstation.toggle_compare().unwrap();
+        assert!(!jstation.is_comparing());
+        assert_eq!(jstation.dsp().amp.gain, gain);
+        assert_eq!(
+            device_edit_buffer(&mut jstation, &mut listener).buf(),
+            edited.buf()
+        );
+
+        // Editing while comparing restores the edited version first.
+        jstation.toggle_compare().unwrap();
+        let bass = dsp::amp::Bass::try_from_raw(10.into()).unwrap();
+        jstation.update_param(dsp::amp::Parameter::from(bass).into());
+        assert!(!jstation.is_comparing());
+        assert_eq!(jstation.dsp().amp.gain, gain);
+        assert_eq!(jstation.dsp().amp.bass, bass);
+    }
+
+    #[test]
+    fn paste_block() {
+        let (mut jstation, mut listener) = connect(Simulator::new());
+        let stored = jstation.edit_buffer().data().clone();
+
+        let other = jstation
+            .get_program(ProgramId::new_user(ProgramNb::try_from(5).unwrap()))
+            .unwrap();
+        let amp = dsp::Block::from_data(dsp::BlockKind::Amp, other.data()).unwrap();
+        let other_modeling = other.data().buf()[9];
+        assert_ne!(stored.buf()[9], other_modeling);
+
+        jstation.paste_block(amp).unwrap();
+        assert!(jstation.has_changed());
+        assert_eq!(jstation.dsp().amp.modeling.raw_value(), other_modeling);
+
+        // The pasted parameters were sent to the device.
+        let device = device_edit_buffer(&mut jstation, &mut listener);
+        assert_eq!(device.buf()[9], other_modeling);
+        // Other blocks are untouched.
+        assert_eq!(device.buf()[..9], stored.buf()[..9]);
+
+        // The whole block is undone at once.
+        jstation.undo().unwrap();
+        assert!(!jstation.has_changed());
+        assert!(!jstation.can_undo());
+    }
+
+    #[test]
+    fn copy_to_user() {
+        let (mut jstation, mut listener) = connect(Simulator::new());
+
+        let factory = ProgramId::new_factory(ProgramNb::try_from(3).unwrap());
+        let user = ProgramId::new_user(ProgramNb::try_from(5).unwrap());
+        let factory_data = jstation.get_program(factory).unwrap().data().clone();
+
+        store(&mut jstation, &mut listener, |jstation| {
+            jstation.copy_to_user(factory, user.nb())
+        })
+        .unwrap();
+        assert_eq!(jstation.cur_prog_id(), Some(user));
+
+        let stored = jstation.get_program(user).unwrap();
+        assert_eq!(stored.data().buf(), factory_data.buf());
+        assert_eq!(stored.name(), factory_data.name());
+        assert!(!jstation.has_changed());
+
+        let unknown = ProgramId::new_user(ProgramNb::try_from(7).unwrap());
+        let mut jstation = JStation::new("test".into());
+        assert!(matches!(
+            jstation.copy_to_user(unknown, user.nb()),
+            Err(Error::ProgramUnknown(prog_id)) if prog_id == unknown,
+        ));
+    }
+
+    #[test]
+    fn offline_changes() {
+        let sim = Simulator::new();
+        let user_2 = ProgramId::new_user(ProgramNb::try_from(1).unwrap());
+        let user_3 = ProgramId::new_user(ProgramNb::try_from(2).unwrap());
+        let user_3_data = sim.get_program(user_3).unwrap().data().clone();
+
+        let mut jstation = JStation::new("test".into());
+
+        // Same as the device Program
+        jstation.change_program(user_3).unwrap();
+        jstation.rename(user_3_data.name());
+        let modeling = dsp::amp::Modeling::try_from_raw(user_3_data.buf()[9]).unwrap();
+        jstation.update_param(dsp::amp::Parameter::from(modeling).into());
+        assert!(jstation.store_to(user_3.nb()).unwrap().is_empty());
+        assert_eq!(jstation.get_program(user_3).unwrap().data(), &user_3_data);
+
+        // Differs from the device Program
+        jstation.change_program(user_2).unwrap();
+        let gain = dsp::amp::Gain::try_from_raw(60.into()).unwrap();
+        jstation.update_param(dsp::amp::Parameter::from(gain).into());
+        assert!(jstation.store_to(user_2.nb()).unwrap().is_empty());
+
+        let _listener = connect_with(&mut jstation, sim);
+        let pending = Vec::from_iter(jstation.pending_bank().iter().map(Program::id));
+        assert_eq!(pending, [user_2]);
+    }
+
+    #[test]
+    fn identify_cur_prog() {
+        let cur = ProgramId::default();
+
+        // A factory copy of the current user Program is ambiguous.
+        let mut sim = Simulator::new();
+        let factory = ProgramId::new_factory(cur.nb());
+        let data = sim.programs[&cur].data().clone();
+        sim.programs.insert(factory, Program::new(factory, data));
+
+        let (jstation, _listener) = connect(sim);
+        assert!(jstation.cur_prog_id().is_none());
+        assert!(jstation.is_origin_unknown());
+
+        // Unique match
+        let (jstation, _listener) = connect(Simulator::new());
+        assert_eq!(jstation.cur_prog_id(), Some(cur));
+        assert!(!jstation.is_origin_unknown());
+
+        // Duplicate user Programs are ambiguous.
+        let mut sim = Simulator::new();
+        let dup = ProgramId::new_user(ProgramNb::try_from(5).unwrap());
+        let data = sim.programs[&cur].data().clone();
+        sim.programs.insert(dup, Program::new(dup, data));
+
+        let (jstation, _listener) = connect(sim);
+        assert!(jstation.cur_prog_id().is_none());
+        assert!(jstation.is_origin_unknown());
+
+        // Edit buffer modified before the connection
+        let mut sim = Simulator::new();
+        sim.has_changed = true;
+
+        let (jstation, _listener) = connect(sim);
+        assert!(jstation.cur_prog_id().is_none());
+        assert!(jstation.is_origin_unknown());
+        assert!(jstation.has_changed());
+    }
+
+    #[test]
+    fn store_program() {
+        let user_2 = ProgramId::new_user(ProgramNb::try_from(1).unwrap());
+
+        let (mut jstation, mut listener) = connect(Simulator::new());
+
+        jstation.change_program(user_2).unwrap();
+        let gain = dsp::amp::Gain::try_from_raw(60.into()).unwrap();
+        jstation.update_param(dsp::amp::Parameter::from(gain).into());
+        let batch = jstation.store_to(user_2.nb()).unwrap();
+
+        // Not stored until acknowledged
+        assert!(jstation.has_changed());
+        assert_ne!(
+            jstation.get_program(user_2).unwrap().data().buf()[..],
+            jstation.edit_buffer().data().buf()[..]
+        );
+
+        let requester = jstation.iface().requester().unwrap();
+        let progs = run(
+            &mut jstation,
+            &mut listener,
+            requester.store_programs(batch),
+        )
+        .unwrap();
+        jstation.have_stored(progs);
+        assert!(!jstation.has_changed());
+        assert_eq!(
+            jstation.get_program(user_2).unwrap().data().buf()[..],
+            jstation.edit_buffer().data().buf()[..]
+        );
+
+        // Negative acknowledgement
+        let mut jstation = JStation::new("test".into());
+        let (mut listener, mut device, _device_in) = connect_loopback(&mut jstation);
+        device
+            .send(
+                &procedure::ToMessageResp {
+                    res: Err(procedure::result::Error::UserProgramNumber {
+                        req_proc: procedure::StoreProgramReq::ID,
+                    }),
+                }
+                .build_for(midi::Channel::from(0)),
+            )
+            .unwrap();
+
+        let err = store(&mut jstation, &mut listener, |jstation| {
+            jstation.store_to(user_2.nb())
+        })
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DeviceRequest {
+                op: Operation::ProgramStore(Some(prog_id)),
+                ..
+            } if prog_id == user_2
+        ));
+        assert!(jstation.get_program(user_2).is_none());
+    }
+
+    #[test]
+    fn store_arrangement() {
+        let user_1 = ProgramId::new_user(ProgramNb::try_from(0).unwrap());
+        let user_2 = ProgramId::new_user(ProgramNb::try_from(1).unwrap());
+
+        let (mut jstation, mut listener) = connect(Simulator::new());
+        let cur_prog_id = jstation.cur_prog_id();
+        let name_1 = jstation.get_program(user_1).unwrap().name().to_string();
+        let name_2 = jstation.get_program(user_2).unwrap().name().to_string();
+        assert_ne!(name_1, name_2);
+
+        let mut arrangement = Arrangement::default();
+        arrangement.swap(user_1.nb(), user_2.nb());
+        jstation.rearrange_bank(&arrangement);
+        assert_eq!(jstation.pending_bank().len(), 2);
+        // Stored through the edit buffer & acknowledged
+        store(&mut jstation, &mut listener, |jstation| {
+            jstation.store_pending_bank()
+        })
+        .unwrap();
+        assert!(jstation.pending_bank().is_empty());
+        assert_eq!(jstation.get_program(user_1).unwrap().name(), name_2);
+        assert_eq!(jstation.get_program(user_2).unwrap().name(), name_1);
+        assert_eq!(jstation.cur_prog_id(), cur_prog_id);
+    }
+
+    #[test]
+    fn error_ack() {
+        let prog_id = ProgramId::new_factory(ProgramNb::try_from(3).unwrap());
+
+        let mut jstation = JStation::new("test".into());
+        let (mut listener, mut device, _device_in) = connect_loopback(&mut jstation);
+        let chan = midi::Channel::from(0);
+
+        // Error acknowledgement for a request
+        device
+            .send(
+                &procedure::ToMessageResp {
+                    res: Err(procedure::result::Error::DataCount {
+                        req_proc: procedure::OneProgramReq::ID,
+                    }),
+                }
+                .build_for(chan),
+            )
+            .unwrap();
+        let requester = jstation.iface().requester().unwrap();
+        let err = run(
+            &mut jstation,
+            &mut listener,
+            requester.request_program(prog_id),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DeviceRequest {
+                op: Operation::ProgramRequest(Some(id)),
+                ..
+            } if id == prog_id
+        ));
+        assert_eq!(
+            err.retry_op(),
+            Some(Operation::ProgramRequest(Some(prog_id)))
+        );
+
+        // Error acknowledgement after the request timed out
+        jstation.iface_mut().set_request_config(RequestConfig {
+            timeout: Duration::from_millis(50),
+            retries: 0,
+            ..Default::default()
+        });
+        let requester = jstation.iface().requester().unwrap();
+        let err = run(
+            &mut jstation,
+            &mut listener,
+            requester.request_program(prog_id),
+        )
+        .unwrap_err();
+        assert!(matches!(err, Error::RequestTimeout(_)));
+
+        // Received while another request for the same procedure is pending
+        let other = ProgramId::new_factory(ProgramNb::try_from(4).unwrap());
+        device
+            .send(
+                &procedure::ToMessageResp {
+                    res: Err(procedure::result::Error::DataCount {
+                        req_proc: procedure::OneProgramReq::ID,
+                    }),
+                }
+                .build_for(chan),
+            )
+            .unwrap();
+        device
+            .send(
+                &procedure::OneProgramResp::from(&Program::new(other, Default::default()))
+                    .build_for(chan),
+            )
+            .unwrap();
+
+        jstation
+            .iface_mut()
+            .set_request_config(RequestConfig::default());
+        let requester = jstation.iface().requester().unwrap();
+        let (late_err, res) = smol::block_on(async {
+            let req = requester.request_program(other);
+            smol::pin!(req);
+
+            let mut late_err = None;
+            loop {
+                let msg = smol::future::or(async { Err(req.as_mut().await) }, async {
+                    Ok(listener.listen().await)
+                })
+                .await;
+
+                match msg {
+                    Ok(Ok(msg)) => jstation.handle_device(msg).unwrap(),
+                    Ok(Err(err)) => late_err = Some(err),
+                    Err(res) => return (late_err, res),
+                }
+            }
+        });
+        // Reported for the request which timed out only
+        assert_eq!(
+            late_err.unwrap().retry_op(),
+            Some(Operation::ProgramRequest(Some(prog_id)))
+        );
+        assert!(res.is_ok());
+
+        // Not retryable
+        let nack = procedure::ToMessageResp {
+            res: Err(procedure::result::Error::SysexChecksum {
+                req_proc: procedure::UtilitySettingsResp::ID,
+            }),
+        }
+        .build_for(chan);
+        let (_, msg) = parse_raw_midi_msg(&nack).unwrap();
+        let err = jstation.handle_device(msg).unwrap_err();
+        assert!(matches!(
+            err,
+            Error::DeviceRequest {
+                op: Operation::UtilitySettingsUpdate,
+                ..
+            }
+        ));
+        assert!(err.retry_op().is_none());
+    }
+
+    #[test]
+    fn slow_bank_dump() {
+        let mut jstation = JStation::new("test".into());
+        let (mut listener, mut device, mut device_in) = connect_loopback(&mut jstation);
+        jstation.iface_mut().set_request_config(RequestConfig {
+            bank_dump_timeout: Duration::from_millis(50),
+            retries: 1,
+            ..Default::default()
+        });
+
+        // Slower than the timeout as a whole, but never silent for that long.
+        let mut sim = Simulator::new();
+        let msgs = sim.handle(&procedure::BankDumpReq.build_for(sim.chan()));
+        let sender = std::thread::spawn(move || {
+            for msg in msgs {
+                std::thread::sleep(Duration::from_millis(10));
+                device.send(&msg).unwrap();
+            }
+        });
+
+        let requester = jstation.iface().requester().unwrap();
+        let received = run(&mut jstation, &mut listener, requester.bank_dump()).unwrap();
+        assert_eq!(received.len(), ProgramNb::enumerate().count());
+        sender.join().unwrap();
+
+        // WhoAmIReq, then the request which is not sent again
+        let procs = parse_procs(Vec::from_iter(std::iter::from_fn(|| {
+            device_in.recv().now_or_never().flatten()
+        })));
+        assert!(matches!(
+            procs.as_slice(),
+            [Procedure::WhoAmIReq(_), Procedure::BankDumpReq(_)]
+        ));
+    }
+
+    #[test]
+    fn request_timeout() {
+        let ((host, host_in), (_device, mut device_in)) = transport::loopback();
+        let mut jstation = JStation::new("test".into());
+        let mut listener = jstation
+            .iface_mut()
+            .connect_transport(host, host_in)
+            .unwrap();
+        jstation.iface_mut().set_request_config(RequestConfig {
+            timeout: Duration::from_millis(10),
+            retries: 1,
+            ..Default::default()
+        });
+
+        let requester = jstation.iface().requester().unwrap();
+        let err = run(
+            &mut jstation,
+            &mut listener,
+            requester.request_utility_settings(),
+        )
+        .unwrap_err();
+        assert!(matches!(
+            err,
+            Error::RequestTimeout(Operation::UtilitySettingsRequest)
+        ));
+        assert_eq!(err.retry_op(), Some(Operation::UtilitySettingsRequest));
+
+        // WhoAmIReq, then the request & its retry
+        let procs = parse_procs(Vec::from_iter(
+            (0..3).map(|_| smol::block_on(device_in.recv()).unwrap()),
+        ));
+        assert!(matches!(procs[0], Procedure::WhoAmIReq(_)));
+        assert!(matches!(procs[1], Procedure::UtilitySettingsReq(_)));
+        assert!(matches!(procs[2], Procedure::UtilitySettingsReq(_)));
+    }
+}
@@ -0,0 +1,256 @@
+use std::{fs, path::Path, sync::Arc};
+
+use crate::{
+    jstation::{
+        parse_raw_midi_msg,
+        procedure::{EndBankDumpResp, OneProgramResp, StartBankDumpResp},
+        Error, Message, Procedure, ProcedureBuilder, Program,
+    },
+    midi,
+};
+
+/// The channel used for the messages written to `.syx` files.
+///
+/// Using `ALL` allows sending the file to a device whatever its channel.
+const FILE_CHAN: midi::Channel = midi::Channel::ALL;
+
+/// Builds a bank dump for the provided `Program`s.
+///
+/// The bank dump follows the same sequence as the one sent by the device:
+/// `StartBankDumpResp`, one `OneProgramResp` per `Program` then `EndBankDumpResp`.
+pub fn build_bank<'a>(progs: impl IntoIterator<Item = &'a Program>) -> Vec<u8> {
+    let progs = Vec::from_iter(
+        progs
+            .into_iter()
+            .map(|prog| OneProgramResp::from(prog).build_for(FILE_CHAN)),
+    );
+
+    let total_len = progs
+        .iter()
+        .map(Vec::len)
+        .sum::<usize>()
+        .try_into()
+        .expect("bank dump length overflow");
+
+    let mut buf = StartBankDumpResp { total_len }.build_for(FILE_CHAN);
+    buf.extend(progs.into_iter().flatten());
+    buf.extend(EndBankDumpResp.build_for(FILE_CHAN));
+
+    buf
+}
+
+/// Exports the provided `Program`s as a bank dump to the `.syx` file at `path`.
+pub fn export_bank<'a>(
+    path: &Path,
+    progs: impl IntoIterator<Item = &'a Program>,
+) -> Result<(), Error> {
+    write(path, build_bank(progs))
+}
+
+/// Parses the `Program`s from the provided buffer.
+///
+/// Messages which can't be parsed are skipped. This can occur
+/// with files produced by other tools, which may contain
+/// procedures we don't support.
+fn parse_programs(buf: &[u8]) -> Vec<Program> {
+    let mut progs = Vec::new();
+
+    let mut i = buf;
+    while !i.is_empty() {
+        match parse_raw_midi_msg(i) {
+            Ok((i_, Message::SysEx(sysex))) => {
+                i = i_;
+
+                match Arc::try_unwrap(sysex).unwrap().proc {
+                    Procedure::OneProgramResp(resp) => progs.push(resp.prog),
+                    Procedure::StartBankDumpResp(_) | Procedure::EndBankDumpResp(_) => (),
+                    other => log::debug!("Ignoring {other:?}"),
+                }
+            }
+            Ok((i_, Message::ChannelVoice(cv))) => {
+                i = i_;
+                log::debug!("Ignoring {cv:?}");
+            }
+            Err(err) => {
+                log::warn!("Skipping unexpected message: {err}");
+
+                // Skip to next sysex message, if any
+                let next = i[1..]
+                    .iter()
+                    .position(|byte| *byte == midi::sysex::TAG)
+                    .map_or(i.len(), |pos| pos + 1);
+                i = &i[next..];
+            }
+        }
+    }
+
+    progs
+}
+
+/// Parses the `Program`s from the provided bank dump.
+pub fn parse_bank(buf: &[u8]) -> Result<Vec<Program>, Error> {
+    let progs = parse_programs(buf);
+    if progs.is_empty() {
+        return Err(Error::ProgramsNotFound);
+    }
+
+    Ok(progs)
+}
+
+/// Imports the `Program`s from the bank dump in the `.syx` file at `path`.
+pub fn import_bank(path: &Path) -> Result<Vec<Program>, Error> {
+    parse_bank(&read(path)?)
+}
+
+/// Builds a single `OneProgramResp` for the provided `Program`.
+pub fn build_program(prog: &Program) -> Vec<u8> {
+    OneProgramResp::from(prog).build_for(FILE_CHAN)
+}
+
+/// Parses the first `Program` from the provided buffer.
+pub fn parse_program(buf: &[u8]) -> Result<Program, Error> {
+    let mut progs = parse_programs(buf).into_iter();
+    let prog = progs.next().ok_or(Error::ProgramsNotFound)?;
+
+    if progs.next().is_some() {
+        log::warn!("Found multiple Programs, using {}", prog.id());
+    }
+
+    Ok(prog)
+}
+
+/// Exports the provided `Program` to the `.syx` file at `path`.
+pub fn export_program(path: &Path, prog: &Program) -> Result<(), Error> {
+    write(path, build_program(prog))
+}
+
+/// Imports the first `Program` from the `.syx` file at `path`.
+pub fn import_program(path: &Path) -> Result<Program, Error> {
+    parse_program(&read(path)?)
+}
+
+fn read(path: &Path) -> Result<Vec<u8>, Error> {
+    fs::read(path)
+        .map_err(|err| Error::with_context(format!("Importing from {}", path.display()), err))
+}
+
+fn write(path: &Path, buf: Vec<u8>) -> Result<(), Error> {
+    fs::write(path, buf)
+        .map_err(|err| Error::with_context(format!("Exporting to {}", path.display()), err))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::jstation::{
+        parse_raw_midi_msg,
+        procedure::{EndBankDumpResp, OneProgramResp, StartBankDumpResp},
+        BufferBuilder, Message, Procedure, ProcedureBuilder, ProcedureId, Program, ProgramData,
+        ProgramId, ProgramNb,
+    };
+
+    fn test_programs() -> Vec<Program> {
+        Vec::from_iter(ProgramNb::enumerate().take(3).map(|nb| {
+            let mut data = ProgramData::default();
+            data.store_name(&format!("Program {nb}"));
+
+            Program::new(ProgramId::new_user(nb), data)
+        }))
+    }
+
+    #[test]
+    fn bank_round_trip() {
+        let progs = test_programs();
+
+        let buf = super::build_bank(progs.iter());
+
+        let mut procs = Vec::new();
+        let mut i = buf.as_slice();
+        while !i.is_empty() {
+            let (i_, msg) = parse_raw_midi_msg(i).unwrap();
+            i = i_;
+
+            let Message::SysEx(sysex) = msg else {
+                panic!("Unexpected {msg:?}");
+            };
+            procs.push(Arc::try_unwrap(sysex).unwrap().proc);
+        }
+
+        assert_eq!(procs.len(), progs.len() + 2);
+
+        let Procedure::StartBankDumpResp(start) = &procs[0] else {
+            panic!("Unexpected {:?}", procs[0]);
+        };
+        let start_end_len = StartBankDumpResp { total_len: 0 }
+            .build_for(super::FILE_CHAN)
+            .len()
+            + EndBankDumpResp.build_for(super::FILE_CHAN).len();
+        assert_eq!(start.total_len as usize, buf.len() - start_end_len);
+
+        for (proc, expected) in procs[1..=progs.len()].iter().zip(progs.iter()) {
+            let Procedure::OneProgramResp(resp) = proc else {
+                panic!("Unexpected {proc:?}");
+            };
+            assert_eq!(resp.prog.id(), expected.id());
+            assert_eq!(resp.prog.name(), expected.name());
+            assert_eq!(resp.prog.data().buf(), expected.data().buf());
+        }
+
+        assert!(matches!(
+            procs.last().unwrap(),
+            Procedure::EndBankDumpResp(_)
+        ));
+
+        let parsed = super::parse_bank(&buf).unwrap();
+        assert_eq!(parsed.len(), progs.len());
+    }
+
+    #[test]
+    fn program_round_trip() {
+        let mut data = ProgramData::default();
+        data.store_name("Single");
+        data.buf_mut()[9] = 5.into();
+        let prog = Program::new(ProgramId::new_user(ProgramNb::try_from(4).unwrap()), data);
+
+        let parsed = super::parse_program(&super::build_program(&prog)).unwrap();
+        assert_eq!(parsed.id(), prog.id());
+        assert_eq!(parsed.name(), prog.name());
+        assert_eq!(parsed.data().buf(), prog.data().buf());
+    }
+
+    #[test]
+    fn j_edit_bank() {
+        let progs = test_programs();
+
+        // J-Edit exports use a length of 4 for the 2 bytes of `total_len`.
+        let mut start = BufferBuilder::new(
+            super::FILE_CHAN,
+            StartBankDumpResp::ID,
+            StartBankDumpResp::VERSION,
+        );
+        start.push_fixed_size_data([4, 0, 0x34, 0x12].into_iter());
+
+        let mut buf = start.build();
+        for prog in progs.iter() {
+            buf.extend(OneProgramResp::from(prog).build_for(super::FILE_CHAN));
+        }
+        buf.extend(EndBankDumpResp.build_for(super::FILE_CHAN));
+
+        let (_, msg) = parse_raw_midi_msg(&buf).unwrap();
+        let Message::SysEx(sysex) = msg else {
+            panic!("Unexpected {msg:?}");
+        };
+        let Procedure::StartBankDumpResp(start) = &sysex.proc else {
+            panic!("Unexpected {:?}", sysex.proc);
+        };
+        assert_eq!(start.total_len, 0x1234);
+
+        let parsed = super::parse_bank(&buf).unwrap();
+        assert_eq!(parsed.len(), progs.len());
+        for (prog, expected) in parsed.iter().zip(progs.iter()) {
+            assert_eq!(prog.id(), expected.id());
+            assert_eq!(prog.name(), expected.name());
+        }
+    }
+}
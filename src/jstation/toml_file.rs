@@ -0,0 +1,50 @@
+//! Helpers for the settings stored in a single TOML file, e.g. the `Setlists`.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use toml::{Table, Value};
+
+use crate::jstation::Error;
+
+/// Returns the default path for `name` in the data directory of current user.
+pub fn default_path(name: &str) -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join("jstation-controller").join(name))
+}
+
+/// Reads the file at `path`.
+///
+/// Returns `Ok(None)` if the file doesn't exist yet.
+pub fn read(path: &Path) -> Result<Option<String>, Error> {
+    match fs::read_to_string(path) {
+        Ok(input) => Ok(Some(input)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(Error::with_context(
+            format!("Reading {}", path.display()),
+            err,
+        )),
+    }
+}
+
+/// Writes `content` to the file at `path`, creating its directory if needed.
+pub fn write(path: &Path, content: String) -> Result<(), Error> {
+    let ctx = || format!("Writing {}", path.display());
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| Error::with_context(ctx(), err))?;
+    }
+
+    fs::write(path, content).map_err(|err| Error::with_context(ctx(), err))
+}
+
+pub fn as_table<'a>(param: &str, value: &'a Value) -> Result<&'a Table, Error> {
+    match value {
+        Value::Table(table) => Ok(table),
+        other => Err(Error::TextValueUnexpected {
+            param: param.to_string(),
+            value: other.to_string(),
+        }),
+    }
+}
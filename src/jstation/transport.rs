@@ -0,0 +1,269 @@
+use iced::futures::{
+    channel::mpsc,
+    future::FusedFuture,
+    stream::{self, BoxStream, Fuse},
+    Stream, StreamExt,
+};
+use std::{fs, path::Path};
+
+use crate::{jstation::Error, midi};
+
+/// The sending half of a connection to a device.
+///
+/// The receiving half is an [`Incoming`].
+pub trait Transport: Send {
+    /// Sends the provided raw MIDI message.
+    fn send(&mut self, msg: &[u8]) -> Result<(), Error>;
+}
+
+/// The receiving half of a connection to a device.
+pub struct Incoming {
+    msg_stream: Fuse<BoxStream<'static, Vec<u8>>>,
+    /// Keeps the source of the messages alive, e.g. a MIDI input connection.
+    _source: Option<Box<dyn Send>>,
+}
+
+impl Incoming {
+    pub fn new(msg_stream: impl Stream<Item = Vec<u8>> + Send + 'static) -> Self {
+        Incoming {
+            msg_stream: msg_stream.boxed().fuse(),
+            _source: None,
+        }
+    }
+
+    /// Builds an `Incoming` which keeps `source` alive as long as it is.
+    pub fn with_source(
+        msg_stream: impl Stream<Item = Vec<u8>> + Send + 'static,
+        source: impl Send + 'static,
+    ) -> Self {
+        Incoming {
+            msg_stream: msg_stream.boxed().fuse(),
+            _source: Some(Box::new(source)),
+        }
+    }
+
+    /// Returns the next raw MIDI message or `None` if the connection is closed.
+    pub fn recv(&mut self) -> impl FusedFuture<Output = Option<Vec<u8>>> + Unpin + '_ {
+        self.msg_stream.next()
+    }
+}
+
+/// A `Transport` using a `midir` output port.
+pub struct Midir(midir::MidiOutputConnection);
+
+impl Midir {
+    pub fn new(midi_out: midir::MidiOutputConnection) -> Self {
+        Midir(midi_out)
+    }
+
+    /// Builds an `Incoming` for the `midir` input port `port_name`.
+    pub fn connect_in(
+        ins: &mut midi::PortsIn,
+        port_name: std::sync::Arc<str>,
+    ) -> Result<Incoming, Error> {
+        let (msg_tx, msg_rx) = mpsc::channel(10);
+        let midi_in = ins.connect(port_name, msg_tx, |_ts, msg, msg_tx| {
+            let _ = msg_tx.try_send(msg.to_owned());
+        })?;
+
+        Ok(Incoming::with_source(msg_rx, midi_in))
+    }
+
+    /// Builds an `Incoming` for the `midir` input port `port_name` which doesn't drop messages.
+    ///
+    /// Use this for ports sending bursts of messages, e.g. a controller
+    /// fader, so that the last value is not lost.
+    pub fn connect_in_unbounded(
+        ins: &mut midi::PortsIn,
+        port_name: std::sync::Arc<str>,
+    ) -> Result<Incoming, Error> {
+        let (msg_tx, msg_rx) = mpsc::unbounded();
+        let midi_in = ins.connect(port_name, msg_tx, |_ts, msg, msg_tx| {
+            let _ = msg_tx.unbounded_send(msg.to_owned());
+        })?;
+
+        Ok(Incoming::with_source(msg_rx, midi_in))
+    }
+}
+
+impl Transport for Midir {
+    fn send(&mut self, msg: &[u8]) -> Result<(), Error> {
+        self.0.send(msg).map_err(|_| Error::MidiSend)
+    }
+}
+
+/// An in-memory `Transport`.
+///
+/// See [`loopback`].
+pub struct Loopback(mpsc::UnboundedSender<Vec<u8>>);
+
+impl Transport for Loopback {
+    fn send(&mut self, msg: &[u8]) -> Result<(), Error> {
+        self.0
+            .unbounded_send(msg.to_owned())
+            .map_err(|_| Error::MidiSend)
+    }
+}
+
+/// Builds an in-memory connection.
+///
+/// Returns both ends of the connection: messages sent using
+/// the `Loopback` of one end are received by the `Incoming`
+/// of the other end. Use one end for the application and
+/// the other for the device, e.g. a `Simulator` or a test.
+pub fn loopback() -> ((Loopback, Incoming), (Loopback, Incoming)) {
+    let (host_tx, device_rx) = mpsc::unbounded();
+    let (device_tx, host_rx) = mpsc::unbounded();
+
+    (
+        (Loopback(host_tx), Incoming::new(host_rx)),
+        (Loopback(device_tx), Incoming::new(device_rx)),
+    )
+}
+
+/// A `Transport` which replays a recorded session.
+///
+/// The recorded messages are all made available to the `Incoming`,
+/// regardless of the messages sent, which are discarded.
+pub struct Replay;
+
+impl Replay {
+    /// Builds a `Replay` for the raw MIDI messages in `buf`.
+    pub fn new(buf: &[u8]) -> (Replay, Incoming) {
+        // Don't close the connection when all messages are consumed
+        let msg_stream = stream::iter(split_raw_midi_msgs(buf)).chain(stream::pending());
+
+        (Replay, Incoming::new(msg_stream))
+    }
+
+    /// Builds a `Replay` for the raw MIDI messages in the file at `path`.
+    pub fn open(path: &Path) -> Result<(Replay, Incoming), Error> {
+        let buf = fs::read(path)
+            .map_err(|err| Error::with_context(format!("Replaying {}", path.display()), err))?;
+
+        Ok(Self::new(&buf))
+    }
+}
+
+impl Transport for Replay {
+    fn send(&mut self, msg: &[u8]) -> Result<(), Error> {
+        log::trace!("Replay: discarding {msg:02x?}");
+
+        Ok(())
+    }
+}
+
+/// Splits the provided buffer in raw MIDI messages.
+///
+/// Running status is not supported. Unexpected bytes are skipped.
+fn split_raw_midi_msgs(mut buf: &[u8]) -> Vec<Vec<u8>> {
+    let mut msgs = Vec::new();
+
+    while let Some(&status) = buf.first() {
+        let len = match status {
+            midi::sysex::TAG => buf
+                .iter()
+                .position(|byte| *byte == midi::sysex::END_TAG)
+                .map_or(buf.len(), |pos| pos + 1),
+            0x80..=0xbf | 0xe0..=0xef => 3,
+            0xc0..=0xdf => 2,
+            _ => {
+                log::warn!("Replay: skipping unexpected byte x{status:02x}");
+                buf = &buf[1..];
+                continue;
+            }
+        };
+
+        let len = len.min(buf.len());
+        msgs.push(buf[..len].to_owned());
+        buf = &buf[len..];
+    }
+
+    msgs
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{Replay, Transport};
+    use crate::{
+        jstation::{
+            prelude::*, procedure, simulator::Simulator, JStation, ProcedureBuilder, ProgramId,
+            ProgramNb,
+        },
+        midi,
+    };
+
+    #[test]
+    fn loopback() {
+        let ((mut host, mut host_in), (mut device, mut device_in)) = super::loopback();
+
+        host.send(&[0xb0, 0x22, 0x10]).unwrap();
+        assert_eq!(
+            smol::block_on(device_in.recv()).unwrap(),
+            vec![0xb0, 0x22, 0x10]
+        );
+
+        device.send(&[0xc0, 0x02]).unwrap();
+        assert_eq!(smol::block_on(host_in.recv()).unwrap(), vec![0xc0, 0x02]);
+
+        drop(device);
+        assert!(smol::block_on(host_in.recv()).is_none());
+    }
+
+    #[test]
+    fn split_raw_midi_msgs() {
+        let sysex = procedure::WhoAmIReq::default().build_for(midi::Channel::ALL);
+        let mut buf = vec![0xb0, 0x22, 0x10];
+        buf.extend(&sysex);
+        // Unexpected byte
+        buf.push(0x12);
+        buf.extend([0xc0, 0x02]);
+
+        let msgs = super::split_raw_midi_msgs(&buf);
+        assert_eq!(msgs, vec![vec![0xb0, 0x22, 0x10], sysex, vec![0xc0, 0x02]]);
+    }
+
+    #[test]
+    fn replay() {
+        // Record a session with the simulator
+        let mut sim = Simulator::new();
+        let chan = sim.chan();
+        let mut session = Vec::new();
+        for req in [
+            procedure::WhoAmIReq::default().build_for(midi::Channel::ALL),
+            procedure::UtilitySettingsReq.build_for(chan),
+            procedure::BankDumpReq.build_for(chan),
+            procedure::ProgramUpdateReq.build_for(chan),
+        ] {
+            session.extend(sim.handle(&req).into_iter().flatten());
+        }
+
+        let (replay, incoming) = Replay::new(&session);
+
+        let mut jstation = JStation::new("test".into());
+        let mut listener = jstation
+            .iface_mut()
+            .connect_transport(replay, incoming)
+            .unwrap();
+
+        while jstation.cur_prog_id().is_none() {
+            let msg = smol::block_on(smol::future::or(
+                async { Some(listener.listen().await.unwrap()) },
+                async {
+                    smol::Timer::after(Duration::from_secs(2)).await;
+                    None
+                },
+            ))
+            .expect("Timeout waiting for replayed message");
+
+            jstation.handle_device(msg).unwrap();
+        }
+
+        assert_eq!(jstation.cur_prog_id(), Some(ProgramId::default()));
+        for nb in ProgramNb::enumerate() {
+            assert!(jstation.get_program(ProgramId::new_user(nb)).is_some());
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod midi;
+
+pub mod jstation;
+
+pub mod ui;
+pub use ui::{app, APP_NAME};
@@ -1,12 +1,7 @@
 // Prevent console window from showing up on Windows
 #![windows_subsystem = "windows"]
 
-mod midi;
-
-mod jstation;
-
-mod ui;
-pub use ui::{app, APP_NAME};
+use jstation_controller::ui;
 
 pub fn main() -> iced::Result {
     // FIXME use tracer instead?
@@ -1,7 +1,17 @@
-use std::{borrow::Cow, cell::RefCell, future, rc::Rc, sync::Arc};
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    future,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+};
 
 use iced::{
-    widget::{column, container, horizontal_space, row, scrollable, vertical_space, Column, Text},
+    widget::{
+        column, container, horizontal_space, progress_bar, row, scrollable, vertical_space, Column,
+        Text,
+    },
     Alignment, Application, Command, Element, Length, Theme,
 };
 use iced_native::command;
@@ -10,12 +20,15 @@ use smol::future::FutureExt;
 
 use crate::jstation::{
     self,
-    data::{dsp, Program, ProgramId, ProgramNb, ProgramsBank},
+    data::{diff, dsp, CCParameter, ParameterDiff, Program, ProgramId, ProgramNb, ProgramsBank},
     prelude::*,
 };
 use crate::midi;
 use crate::ui::{self, style, widget};
 
+/// Width of the modals listing `ParameterDiff`s.
+const DIFF_MODAL_WIDTH: f32 = 550f32;
+
 pub static APP_NAME: Lazy<Arc<str>> = Lazy::new(|| "J-Station Controller".into());
 
 static PROGRAMS_BANKS: Lazy<Cow<'static, [ProgramsBank]>> =
@@ -26,10 +39,25 @@ pub struct App {
 
     ports: Rc<RefCell<ui::midi::Ports>>,
     scanner_ctx: Option<midi::scanner::Context>,
+    controller: ui::controller::Controller,
+    setups: Option<jstation::controller::Setups>,
+    learn: ui::controller::Learn,
 
     panel: Panel,
+    file_path: String,
+    library: Option<jstation::Library>,
+    show_library: bool,
+    library_filter: String,
+    library_form: Option<ui::library::Form>,
+    block_form: ui::block::Form,
+    clipboard: Option<ui::block::Clipboard>,
+    organizer: ui::organizer::Form,
+    factory_copy: ui::factory::Form,
+    setlists: ui::setlist::State,
     use_dark_them: bool,
     status_text: Cow<'static, str>,
+    /// The failed device `Operation` which can be retried.
+    retry_op: Option<jstation::Operation>,
 }
 
 impl App {
@@ -58,14 +86,59 @@ impl App {
                             .expect("Not connected");
                         self.ports.borrow_mut().set_ports(port_in, port_out);
 
-                        return Ok(Command::single(command::Action::Future(
-                            future::ready(Message::HideModal).boxed(),
-                        )));
+                        return Ok(Command::batch([
+                            Command::single(command::Action::Future(
+                                future::ready(Message::HideModal).boxed(),
+                            )),
+                            self.spawn_sync(),
+                        ]));
+                    }
+                    NotifyStore(ref notif) => {
+                        let prog_id = ProgramId::new_user(notif.nb);
+                        self.jstation.handle_device(SysEx(sysex))?;
+
+                        return Ok(self.spawn_request(
+                            move |requester| async move {
+                                requester.request_program(prog_id).await.map(drop)
+                            },
+                            Message::Request,
+                        ));
+                    }
+                    NotifyUtility(_) => {
+                        self.jstation.handle_device(SysEx(sysex))?;
+
+                        return Ok(self.spawn_request(
+                            |requester| async move {
+                                requester.request_utility_settings().await.map(drop)
+                            },
+                            Message::Request,
+                        ));
+                    }
+                    EndBankDumpResp(_) => {
+                        self.jstation.handle_device(SysEx(sysex))?;
+
+                        if !self.jstation.pending_bank().is_empty() {
+                            // Offer to sync the Programs stored while offline.
+                            self.panel = Panel::PendingBank;
+                        }
                     }
                     _ => self.jstation.handle_device(SysEx(sysex))?,
                 }
             }
-            Ok(ChannelVoice(cv)) => self.jstation.handle_device(ChannelVoice(cv))?,
+            Ok(ChannelVoice(cv)) => {
+                let is_prog_change =
+                    matches!(cv.msg, jstation::channel_voice::Message::ProgramChange(_));
+                let prev_prog_id = self.jstation.cur_prog_id();
+                self.jstation.handle_device(ChannelVoice(cv))?;
+
+                if is_prog_change {
+                    if self.panel.is_live() {
+                        return self.follow_footswitch(prev_prog_id);
+                    }
+
+                    return Ok(self.request_missing_program());
+                }
+            }
             Err(err) if err.is_handshake_timeout() => {
                 if let Some(scanner_ctx) = self.scanner_ctx.take() {
                     self.scanner_ctx = self.jstation.scan_next(scanner_ctx);
@@ -92,18 +165,414 @@ impl App {
         Ok(Command::none())
     }
 
+    /// Spawns the device request built by `req`.
+    ///
+    /// The result is reported using the `Message` built by `on_done`.
+    fn spawn_request<T, F>(
+        &self,
+        req: impl FnOnce(jstation::Requester) -> F,
+        on_done: fn(Result<T, jstation::Error>) -> Message,
+    ) -> Command<Message>
+    where
+        T: 'static,
+        F: future::Future<Output = Result<T, jstation::Error>> + Send + 'static,
+    {
+        match self.jstation.iface().requester() {
+            Ok(requester) => {
+                let req = req(requester);
+                Command::single(command::Action::Future(
+                    async move { on_done(req.await) }.boxed(),
+                ))
+            }
+            Err(err) => {
+                log::debug!("Can't send request: {err}");
+                Command::none()
+            }
+        }
+    }
+
+    /// Stores the `Program`s of `batch` to the device.
+    fn spawn_store(&self, batch: jstation::StoreBatch) -> Command<Message> {
+        if batch.is_empty() {
+            return Command::none();
+        }
+
+        self.spawn_request(
+            move |requester| requester.store_programs(batch),
+            Message::Stored,
+        )
+    }
+
+    /// Synchronizes with the device, skipping the cached `Program`s.
+    fn spawn_sync(&self) -> Command<Message> {
+        let cached = self.jstation.cached_programs();
+        self.spawn_request(
+            move |requester| requester.with_cached(cached).sync_device(),
+            Message::Synced,
+        )
+    }
+
+    /// Requests current `Program` from the device if it is unknown.
+    fn request_missing_program(&self) -> Command<Message> {
+        match self.jstation.cur_prog_id() {
+            Some(prog_id) if self.jstation.get_program(prog_id).is_none() => self.spawn_request(
+                move |requester| async move { requester.request_program(prog_id).await.map(drop) },
+                Message::Request,
+            ),
+            _ => Command::none(),
+        }
+    }
+
+    /// Switches to the `Song` `offset` steps away in the live view.
+    fn change_song(&mut self, offset: isize) -> Result<Command<Message>, Error> {
+        let Some(prog_id) = self.setlists.step(offset).map(|song| song.prog_id) else {
+            return Ok(Command::none());
+        };
+
+        self.jstation.change_program(prog_id)?;
+
+        Ok(self.request_missing_program())
+    }
+
+    /// Follows a `Program` change from the device footswitch in the live view.
+    ///
+    /// If the new `Program` is not in the `Setlist`, switches to the next
+    /// or previous `Song` depending on the direction of the change
+    /// from `prev_prog_id`, the `Program` before the change.
+    fn follow_footswitch(
+        &mut self,
+        prev_prog_id: Option<ProgramId>,
+    ) -> Result<Command<Message>, Error> {
+        let (Some(prog_id), Some(setlist), Some(song)) = (
+            self.jstation.cur_prog_id(),
+            self.setlists.setlist(),
+            self.setlists.live_song(),
+        ) else {
+            return Ok(self.request_missing_program());
+        };
+
+        if prog_id == song.prog_id {
+            return Ok(self.request_missing_program());
+        }
+
+        if let Some(idx) = setlist
+            .songs
+            .iter()
+            .position(|song| song.prog_id == prog_id)
+        {
+            self.setlists.live_song = idx;
+            return Ok(self.request_missing_program());
+        }
+
+        let prev_nb = prev_prog_id.unwrap_or(song.prog_id).nb();
+        let offset = if prog_id.nb().is_ahead_of(prev_nb) {
+            1
+        } else {
+            -1
+        };
+        self.change_song(offset)
+    }
+
     fn set_status(&mut self, status: impl Into<Cow<'static, str>>) {
         self.status_text = status.into();
     }
 
     fn clear_status(&mut self) {
         self.status_text = Default::default();
+        self.retry_op = None;
     }
 
     fn show_error(&mut self, err: impl ToString) {
         let err = err.to_string();
         log::error!("{err}");
         self.status_text = err.into();
+        self.retry_op = None;
+    }
+
+    fn open_library(&mut self) {
+        let Some(dir) = jstation::Library::default_dir() else {
+            self.show_error("Couldn't find a directory for the library");
+            return;
+        };
+
+        match jstation::Library::open(dir) {
+            Ok(library) => self.library = Some(library),
+            Err(err) => self.show_error(err),
+        }
+    }
+
+    fn open_setlists(&mut self) {
+        let Some(path) = jstation::Setlists::default_path() else {
+            self.show_error("Couldn't find a directory for the setlists");
+            return;
+        };
+
+        match jstation::Setlists::open(path) {
+            Ok(setlists) => self.setlists.setlists = Some(setlists),
+            Err(err) => self.show_error(err),
+        }
+    }
+
+    fn open_setups(&mut self) {
+        let Some(path) = jstation::controller::Setups::default_path() else {
+            self.show_error("Couldn't find a directory for the controller setups");
+            return;
+        };
+
+        match jstation::controller::Setups::open(path) {
+            Ok(setups) => self.setups = Some(setups),
+            Err(err) => self.show_error(err),
+        }
+    }
+
+    /// Handles a `Control` moved on the controller.
+    ///
+    /// The `Control` is mapped to the parameter awaiting a `Control` when learning,
+    /// otherwise the value is forwarded to the mapped parameter.
+    fn handle_control(
+        &mut self,
+        control: jstation::controller::Control,
+        value: midi::CCValue,
+    ) -> Result<(), Error> {
+        let (Some(port), Some(setups)) = (self.controller.cur(), self.setups.as_mut()) else {
+            return Ok(());
+        };
+
+        if let ui::controller::Learn::AwaitingControl(param) = self.learn {
+            let Some(cc) = param.to_cc() else {
+                return Ok(());
+            };
+
+            setups.get_or_insert(&port).learn(control, cc.nb);
+            setups.save()?;
+
+            self.learn = ui::controller::Learn::Off;
+            let (block, name) = param.names();
+            self.set_status(format!(
+                "Mapped CC {} on channel {} to {block} {name}",
+                control.nb, control.chan,
+            ));
+
+            return Ok(());
+        }
+
+        let Some(mapping) = setups.get(&port).and_then(|setup| setup.get(control)) else {
+            return Ok(());
+        };
+
+        if let Some(param) = mapping.translate(self.jstation.dsp(), value)? {
+            self.jstation.update_param(param);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the label for the `Program` of `song`.
+    fn song_label(&self, song: &jstation::Song) -> String {
+        format!("{} {}", song.prog_id, self.program_label(song.prog_id))
+    }
+
+    fn library_entry(&self) -> Option<&jstation::library::Entry> {
+        let form = self.library_form.as_ref()?;
+        self.library.as_ref()?.find(&form.path)
+    }
+
+    /// Reports that the `Organizer` action can't apply to the selected slots.
+    fn refuse_arrangement(&mut self) {
+        self.set_status(format!(
+            "Can't {} unknown or unreadable Programs",
+            self.organizer.action.to_string().to_lowercase(),
+        ));
+    }
+
+    /// Returns the label for `prog_id` in the `Program` lists.
+    fn program_label(&self, prog_id: ProgramId) -> &str {
+        if self.jstation.is_unreadable(prog_id) {
+            "(unreadable)"
+        } else if self.jstation.is_empty_slot(prog_id) {
+            "(empty)"
+        } else {
+            self.jstation.get_program(prog_id).map_or("", Program::name)
+        }
+    }
+
+    fn store_to_list(&self, on_press: fn(ProgramNb) -> Message) -> Element<'_, Message> {
+        scrollable(Column::with_children(
+            ProgramNb::enumerate()
+                .map(|prog_nb| {
+                    let prog_id = ProgramId::new_user(prog_nb);
+
+                    let style = if self
+                        .jstation
+                        .cur_prog_id()
+                        .map_or(false, |cur_prog_id| cur_prog_id.nb() == prog_id.nb())
+                    {
+                        ui::style::Button::ListItemSelected
+                    } else if self.jstation.is_empty_slot(prog_id) {
+                        ui::style::Button::ListItemEmpty
+                    } else {
+                        ui::style::Button::ListItem
+                    };
+
+                    iced::widget::Button::new(row![
+                        ui::value_label(prog_id.nb().to_string()),
+                        horizontal_space(Length::Fixed(5f32)),
+                        ui::value_label(self.program_label(prog_id)).width(Length::Fill),
+                    ])
+                    .on_press(on_press(prog_id.nb()))
+                    .style(style.into())
+                    .into()
+                })
+                .collect(),
+        ))
+        .into()
+    }
+
+    /// Lists the parameters which differ from the `Program` stored at `prog_nb`.
+    ///
+    /// `columns` are the labels for the stored & the new values.
+    fn diff_list(
+        &self,
+        prog_nb: ProgramNb,
+        diffs: impl FnOnce(&jstation::ProgramData) -> Result<Vec<ParameterDiff>, jstation::Error>,
+        columns: (&str, &str),
+    ) -> Element<'_, Message> {
+        let prog_id = ProgramId::new_user(prog_nb);
+
+        let diffs: Element<_> = match self.jstation.get_program(prog_id) {
+            Some(prog) if !self.jstation.is_empty_slot(prog_id) => match diffs(prog.data()) {
+                Ok(diffs) if diffs.is_empty() => ui::value_label("No differences").into(),
+                Ok(diffs) => scrollable(Column::with_children(
+                    diffs
+                        .into_iter()
+                        .map(|diff| {
+                            row![
+                                ui::value_label(diff.block).width(Length::Fixed(90f32)),
+                                ui::value_label(diff.name).width(Length::Fixed(90f32)),
+                                ui::value_label(diff.from.as_deref().unwrap_or("-"))
+                                    .width(Length::Fill),
+                                ui::value_label(diff.to.as_deref().unwrap_or("-"))
+                                    .width(Length::Fill),
+                            ]
+                            .into()
+                        })
+                        .collect(),
+                ))
+                .height(Length::Fixed(400f32))
+                .into(),
+                Err(err) => ui::value_label(err).into(),
+            },
+            Some(_) => ui::value_label("Empty slot").into(),
+            None => ui::value_label("Program not loaded").into(),
+        };
+
+        column![
+            row![
+                ui::value_label("Block").width(Length::Fixed(90f32)),
+                ui::value_label("Parameter").width(Length::Fixed(90f32)),
+                ui::value_label(columns.0).width(Length::Fill),
+                ui::value_label(columns.1).width(Length::Fill),
+            ],
+            vertical_space(Length::Fixed(5f32)),
+            diffs,
+        ]
+        .into()
+    }
+
+    /// Shows the parameters which differ from the `Program` stored at `prog_nb`.
+    ///
+    /// `on_store` is sent if the user confirms.
+    fn confirm_store(
+        &self,
+        prog_nb: ProgramNb,
+        diffs: impl FnOnce(&jstation::ProgramData) -> Result<Vec<ParameterDiff>, jstation::Error>,
+        on_store: Message,
+    ) -> Element<'_, Message> {
+        ui::modal_with_width(
+            &format!("Store to {prog_nb}?"),
+            column![
+                self.diff_list(prog_nb, diffs, ("Stored", "New")),
+                vertical_space(Length::Fixed(20f32)),
+                row![
+                    ui::button("Cancel")
+                        .on_press(Message::HideModal)
+                        .style(style::Button::Default.into()),
+                    horizontal_space(Length::Fixed(10f32)),
+                    ui::button("Store")
+                        .on_press(on_store)
+                        .style(style::Button::Active.into()),
+                ],
+            ]
+            .align_items(Alignment::End),
+            Message::HideModal,
+            DIFF_MODAL_WIDTH,
+        )
+        .into()
+    }
+
+    fn status_bar(&self) -> Element<'_, Message> {
+        let progress = match (
+            self.jstation.bank_dump_progress(),
+            self.jstation.fetch_progress(),
+        ) {
+            (Some(progress), _) => Some((
+                format!(
+                    "Receiving Programs {}/{}",
+                    progress.received,
+                    progress.expected(),
+                ),
+                progress.ratio(),
+            )),
+            (None, Some(progress)) => Some((
+                format!(
+                    "Requesting Programs {}/{}",
+                    progress.received, progress.expected,
+                ),
+                progress.ratio(),
+            )),
+            (None, None) => None,
+        };
+
+        let status: Element<_> = match progress {
+            Some((label, ratio)) => row![
+                ui::value_label(label),
+                horizontal_space(Length::Fixed(10f32)),
+                progress_bar(0f32..=1f32, ratio)
+                    .width(Length::Fixed(200f32))
+                    .height(Length::Fixed(10f32)),
+                horizontal_space(Length::Fill),
+            ]
+            .align_items(Alignment::Center)
+            .into(),
+            None => Text::new(self.status_text.clone())
+                .size(18)
+                .width(Length::Fill)
+                .into(),
+        };
+
+        row![
+            status,
+            self.retry_op.map_or_else(
+                || Element::from(horizontal_space(Length::Shrink)),
+                |_| ui::button("Retry")
+                    .on_press(Message::Retry)
+                    .style(style::Button::Default.into())
+                    .into(),
+            ),
+            if self.learn.is_on() {
+                Element::from(
+                    ui::button("Stop learning")
+                        .on_press(Message::StopLearn)
+                        .style(style::Button::Default.into()),
+                )
+            } else {
+                horizontal_space(Length::Shrink).into()
+            },
+            horizontal_space(Length::Fixed(10f32)),
+            ui::checkbox("Dark Theme", self.use_dark_them, Message::UseDarkTheme),
+        ]
+        .align_items(Alignment::Center)
+        .into()
     }
 
     fn refresh_ports(&mut self) {
@@ -126,13 +595,30 @@ impl Application for App {
 
             ports: RefCell::new(ui::midi::Ports::default()).into(),
             scanner_ctx: None,
+            controller: Default::default(),
+            setups: None,
+            learn: Default::default(),
 
             panel: Panel::default(),
+            file_path: String::new(),
+            library: None,
+            show_library: false,
+            library_filter: String::new(),
+            library_form: None,
+            block_form: Default::default(),
+            clipboard: None,
+            organizer: Default::default(),
+            factory_copy: Default::default(),
+            setlists: Default::default(),
             use_dark_them: true,
             status_text: Default::default(),
+            retry_op: None,
         };
 
         app.refresh_ports();
+        app.open_library();
+        app.open_setlists();
+        app.open_setups();
 
         (
             app,
@@ -157,27 +643,186 @@ impl Application for App {
     fn update(&mut self, event: Message) -> Command<Message> {
         use Message::*;
         let res = match event {
+            // Keep the status so device errors remain visible.
             JStation(res) => match self.handle_device_evt(res) {
-                Ok(cmd) => {
-                    self.clear_status();
-                    return cmd;
-                }
+                Ok(cmd) => return cmd,
                 Err(err) => Err(err),
             },
             Parameter(param) => {
                 self.jstation.update_param(param);
+
+                if matches!(self.learn, ui::controller::Learn::AwaitingParameter) {
+                    // Only the params with a CC can be controlled.
+                    if param.to_cc().is_some() {
+                        self.learn = ui::controller::Learn::AwaitingControl(param);
+                        let (block, name) = param.names();
+                        self.set_status(format!("Move a control for {block} {name}"));
+                    }
+
+                    return Command::none();
+                }
+
                 Ok(())
             }
-            SelectProgram(prog_id) => self.jstation.change_program(prog_id).map_err(Into::into),
+            Control((control, value)) => match self.handle_control(control, value) {
+                // Keep the status, e.g. the result of the learn.
+                Ok(()) => return Command::none(),
+                Err(err) => Err(err),
+            },
+            ShowController => {
+                if let Err(err) = self.controller.refresh() {
+                    self.show_error(format!("Midi ports not found: {err}"));
+                }
+
+                self.panel = Panel::Controller;
+                return Command::none();
+            }
+            ConnectController(port) => self.controller.connect(port).map_err(Into::into),
+            DisconnectController => {
+                self.learn = ui::controller::Learn::Off;
+                self.controller.disconnect().map_err(Into::into)
+            }
+            RemoveMapping(control) => match (self.controller.cur(), self.setups.as_mut()) {
+                (Some(port), Some(setups)) => {
+                    setups.get_or_insert(&port).remove(control);
+                    setups.save().map_err(Into::into)
+                }
+                _ => Ok(()),
+            },
+            StartLearn => {
+                self.learn = ui::controller::Learn::AwaitingParameter;
+                self.panel = Panel::Main;
+                self.set_status("Touch the parameter to control");
+                return Command::none();
+            }
+            StopLearn => {
+                self.learn = ui::controller::Learn::Off;
+                Ok(())
+            }
+            SelectProgram(prog_id) => match self.jstation.change_program(prog_id) {
+                Ok(()) => {
+                    self.clear_status();
+                    return self.request_missing_program();
+                }
+                Err(err) => Err(err.into()),
+            },
             StoreTo(prog_nb) => {
                 self.panel = Panel::Main;
-                self.jstation.store_to(prog_nb).map_err(Into::into)
+                match self.jstation.store_to(prog_nb) {
+                    Ok(batch) => {
+                        self.clear_status();
+                        return self.spawn_store(batch);
+                    }
+                    Err(err) => Err(err.into()),
+                }
             }
             ShowStoreTo => {
                 self.panel = Panel::StoreTo;
                 Ok(())
             }
+            ConfirmStoreTo(prog_nb) => {
+                self.panel = Panel::ConfirmStoreTo(prog_nb);
+                Ok(())
+            }
+            ShowBlocks => {
+                self.panel = Panel::Blocks;
+                Ok(())
+            }
+            SelectBlockKind(kind) => {
+                self.block_form.kind = kind;
+                Ok(())
+            }
+            SelectBlockSource(source) => {
+                self.block_form.source = source;
+                Ok(())
+            }
+            CopyBlock => {
+                use ui::block::Source;
+
+                let kind = self.block_form.kind;
+                let block = match &self.block_form.source {
+                    Source::EditBuffer => Ok(Some(self.jstation.dsp().block(kind))),
+                    Source::Program(prog_id, _) => self
+                        .jstation
+                        .get_program(*prog_id)
+                        .map(|prog| dsp::Block::from_data(kind, prog.data()))
+                        .transpose(),
+                    Source::Library(path, _) => self
+                        .library
+                        .as_ref()
+                        .and_then(|library| library.find(path))
+                        .map(|entry| dsp::Block::from_data(kind, entry.data()))
+                        .transpose(),
+                };
+
+                block
+                    .map(|block| {
+                        if let Some(block) = block {
+                            self.clipboard = Some(ui::block::Clipboard {
+                                block,
+                                origin: self.block_form.source.to_string(),
+                            });
+                        }
+                    })
+                    .map_err(Into::into)
+            }
+            PasteBlock => match self.clipboard.as_ref() {
+                Some(clipboard) => self.jstation.paste_block(clipboard.block),
+                None => Ok(()),
+            }
+            .map_err(Into::into),
+            SwapBlock => match self.clipboard.as_ref().map(|clipboard| clipboard.block) {
+                // The clipboard receives the block replaced in the edit buffer.
+                Some(block) => {
+                    let prev = self.jstation.dsp().block(block.kind());
+                    self.jstation.paste_block(block).map(|()| {
+                        self.clipboard = Some(ui::block::Clipboard {
+                            block: prev,
+                            origin: ui::block::Source::EditBuffer.to_string(),
+                        });
+                    })
+                }
+                None => Ok(()),
+            }
+            .map_err(Into::into),
+            Revert => self.jstation.revert().map_err(Into::into),
+            Compare => self.jstation.toggle_compare().map_err(Into::into),
             Undo => self.jstation.undo().map_err(Into::into),
+            Redo => self.jstation.redo().map_err(Into::into),
+            Retry => {
+                if let Some(op) = self.retry_op.take() {
+                    self.clear_status();
+                    let cached = self.jstation.cached_programs();
+                    return self.spawn_request(
+                        move |requester| requester.with_cached(cached).retry(op),
+                        Synced,
+                    );
+                }
+
+                Ok(())
+            }
+            // Keep the status on success, e.g. "Found J-Station".
+            Request(Ok(())) => return Command::none(),
+            Request(Err(err)) => Err(err.into()),
+            Stored(Ok(progs)) => {
+                match progs.len() {
+                    1 => self.set_status("Program stored"),
+                    count => self.set_status(format!("{count} Programs stored")),
+                }
+                self.jstation.have_stored(progs);
+
+                return Command::none();
+            }
+            Stored(Err(err)) => Err(err.into()),
+            Synced(Ok(unreadable)) => {
+                if !unreadable.is_empty() {
+                    self.set_status(format!("{} Programs couldn't be read", unreadable.len()));
+                }
+                self.jstation.add_unreadable(unreadable);
+
+                return Command::none();
+            }
+            Synced(Err(err)) => Err(err.into()),
             Rename(name) => {
                 self.jstation.rename(name);
                 Ok(())
@@ -187,13 +832,315 @@ impl Application for App {
                     let _ = self.jstation.tuner_off();
                 }
 
+                let res = if matches!(self.panel, Panel::Setlists) {
+                    self.setlists.commit().map_err(Into::into)
+                } else {
+                    Ok(())
+                };
+
                 self.panel = Panel::Main;
-                Ok(())
+                res
             }
             SelectProgramsBank(bank) => {
                 self.jstation.select_bank(bank);
                 Ok(())
             }
+            ToggleLibrary => {
+                self.show_library = !self.show_library;
+                Ok(())
+            }
+            LibraryFilter(filter) => {
+                self.library_filter = filter;
+                Ok(())
+            }
+            AddToLibrary => match self.library.as_mut() {
+                Some(library) => {
+                    let data = self.jstation.edit_buffer().data().clone();
+                    library.add(data, Default::default()).map(|entry| {
+                        self.library_form = Some(ui::library::Form::new(entry));
+                        self.panel = Panel::LibraryEntry;
+                    })
+                }
+                None => Ok(()),
+            }
+            .map_err(Into::into),
+            SelectLibraryEntry(path) => {
+                if let Some(entry) = self.library.as_ref().and_then(|lib| lib.find(&path)) {
+                    self.library_form = Some(ui::library::Form::new(entry));
+                    self.panel = Panel::LibraryEntry;
+                }
+                Ok(())
+            }
+            LibraryAuthor(author) => {
+                if let Some(form) = self.library_form.as_mut() {
+                    form.author = author;
+                }
+                Ok(())
+            }
+            LibraryTags(tags) => {
+                if let Some(form) = self.library_form.as_mut() {
+                    form.tags = tags;
+                }
+                Ok(())
+            }
+            LibraryNotes(notes) => {
+                if let Some(form) = self.library_form.as_mut() {
+                    form.notes = notes;
+                }
+                Ok(())
+            }
+            SaveLibraryEntry => {
+                self.panel = Panel::Main;
+                match (self.library.as_mut(), self.library_form.take()) {
+                    (Some(library), Some(form)) => library
+                        .update_meta(&form.path, form.metadata())
+                        .map_err(Into::into),
+                    _ => Ok(()),
+                }
+            }
+            ShowRemoveLibraryEntry => {
+                self.panel = Panel::RemoveLibraryEntry;
+                Ok(())
+            }
+            RemoveLibraryEntry => {
+                self.panel = Panel::Main;
+                match (self.library.as_mut(), self.library_form.take()) {
+                    (Some(library), Some(form)) => library.remove(&form.path).map_err(Into::into),
+                    _ => Ok(()),
+                }
+            }
+            SendLibraryEntry => {
+                self.panel = Panel::Main;
+                match self.library_entry().map(|entry| entry.data().clone()) {
+                    Some(data) => self.jstation.send_to_edit_buffer(&data).map_err(Into::into),
+                    None => Ok(()),
+                }
+            }
+            ShowLibraryStoreTo => {
+                self.panel = Panel::LibraryStoreTo;
+                Ok(())
+            }
+            ConfirmLibraryStoreTo(prog_nb) => {
+                self.panel = Panel::ConfirmLibraryStoreTo(prog_nb);
+                Ok(())
+            }
+            LibraryStoreTo(prog_nb) => {
+                self.panel = Panel::Main;
+                let Some(data) = self.library_entry().map(|entry| entry.data().clone()) else {
+                    return Command::none();
+                };
+
+                match self
+                    .jstation
+                    .send_to_edit_buffer(&data)
+                    .and_then(|()| self.jstation.store_to(prog_nb))
+                {
+                    Ok(batch) => {
+                        self.clear_status();
+                        return self.spawn_store(batch);
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+            ShowFile => {
+                self.panel = Panel::File;
+                Ok(())
+            }
+            RefreshPrograms => {
+                self.clear_status();
+                return self.spawn_request(jstation::Requester::refresh_user_programs, Synced);
+            }
+            FilePath(path) => {
+                self.file_path = path;
+                Ok(())
+            }
+            ExportBank => {
+                let path = Path::new(&self.file_path);
+                match self.jstation.export_bank(path) {
+                    Ok(()) => {
+                        self.panel = Panel::Main;
+                        self.set_status(format!("Exported bank to {}", path.display()));
+
+                        return Command::none();
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+            ExportProgram => {
+                let path = Path::new(&self.file_path);
+                match self.jstation.export_program(path) {
+                    Ok(()) => {
+                        self.panel = Panel::Main;
+                        self.set_status(format!("Exported Program to {}", path.display()));
+
+                        return Command::none();
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+            ImportProgram => {
+                self.panel = Panel::Main;
+                self.jstation
+                    .import_program(Path::new(&self.file_path))
+                    .map_err(Into::into)
+            }
+            ExportPreset => {
+                let path = Path::new(&self.file_path);
+                match self.jstation.export_preset(path) {
+                    Ok(()) => {
+                        self.panel = Panel::Main;
+                        self.set_status(format!("Exported preset to {}", path.display()));
+
+                        return Command::none();
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+            ImportPreset => {
+                self.panel = Panel::Main;
+                self.jstation
+                    .import_preset(Path::new(&self.file_path))
+                    .map_err(Into::into)
+            }
+            ImportBank => {
+                let path = Path::new(&self.file_path);
+                self.jstation
+                    .import_bank(path)
+                    .map(|()| {
+                        self.panel = Panel::PendingBank;
+                    })
+                    .map_err(Into::into)
+            }
+            StorePendingBank => {
+                self.panel = Panel::Main;
+                self.organizer = Default::default();
+                match self.jstation.store_pending_bank() {
+                    Ok(batch) => {
+                        self.clear_status();
+                        return self.spawn_store(batch);
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+            ShowCopyToUser => {
+                let factory = match self.jstation.cur_prog_id() {
+                    Some(prog_id) if prog_id.bank().is_factory() => prog_id.nb(),
+                    _ => self.factory_copy.factory,
+                };
+                self.factory_copy = ui::factory::Form {
+                    factory,
+                    user: None,
+                };
+                self.panel = Panel::CopyToUser;
+                Ok(())
+            }
+            SelectCopySource(prog_nb) => {
+                self.factory_copy.factory = prog_nb;
+                Ok(())
+            }
+            SelectCopyTarget(prog_nb) => {
+                self.factory_copy.user = Some(prog_nb);
+                Ok(())
+            }
+            CopyToUser => {
+                self.panel = Panel::Main;
+                let ui::factory::Form { factory, user } = self.factory_copy;
+                let Some(user) = user else {
+                    return Command::none();
+                };
+
+                match self
+                    .jstation
+                    .copy_to_user(ProgramId::new_factory(factory), user)
+                {
+                    Ok(batch) => {
+                        self.clear_status();
+                        return self.spawn_store(batch);
+                    }
+                    Err(err) => Err(err.into()),
+                }
+            }
+            ShowOrganizer => {
+                self.panel = Panel::Organizer;
+                Ok(())
+            }
+            ShowSetlists => {
+                self.panel = Panel::Setlists;
+                Ok(())
+            }
+            Setlist(evt) => self.setlists.update(evt).map_err(Into::into),
+            GoLive => {
+                if let Err(err) = self.setlists.commit() {
+                    self.show_error(err);
+                    return Command::none();
+                }
+
+                self.setlists.live_song = 0;
+                self.panel = Panel::Live;
+                match self.change_song(0) {
+                    Ok(cmd) => {
+                        self.clear_status();
+                        return cmd;
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            NextSong | PreviousSong if self.panel.is_live() => {
+                let offset = if matches!(event, NextSong) { 1 } else { -1 };
+                match self.change_song(offset) {
+                    Ok(cmd) => {
+                        self.clear_status();
+                        return cmd;
+                    }
+                    Err(err) => Err(err),
+                }
+            }
+            NextSong | PreviousSong => return Command::none(),
+            ExitLive => {
+                self.panel = Panel::Setlists;
+                Ok(())
+            }
+            OrganizerAction(action) => {
+                self.organizer.action = action;
+                Ok(())
+            }
+            OrganizerGrab(prog_nb) => {
+                let is_known = |nb| self.jstation.get_program(ProgramId::new_user(nb)).is_some();
+                if !self.organizer.grab(prog_nb, is_known) {
+                    self.refuse_arrangement();
+                    return Command::none();
+                }
+
+                Ok(())
+            }
+            OrganizerDrop(prog_nb) => {
+                let is_known = |nb| self.jstation.get_program(ProgramId::new_user(nb)).is_some();
+                if !self.organizer.drop_on(prog_nb, is_known) {
+                    self.refuse_arrangement();
+                    return Command::none();
+                }
+
+                Ok(())
+            }
+            ResetArrangement => {
+                self.organizer = Default::default();
+                Ok(())
+            }
+            PreviewArrangement => {
+                self.jstation.rearrange_bank(&self.organizer.arrangement);
+                if self.jstation.pending_bank().is_empty() {
+                    self.set_status("No Programs to store");
+                    return Command::none();
+                }
+
+                self.panel = Panel::PendingBank;
+                Ok(())
+            }
+            DiscardPendingBank => {
+                self.jstation.discard_pending_bank();
+                self.panel = Panel::Main;
+                Ok(())
+            }
             StartScan => {
                 log::debug!("Scanning Midi ports for J-Station");
                 self.scanner_ctx = self.jstation.start_scan();
@@ -242,14 +1189,48 @@ impl Application for App {
 
         match res {
             Ok(()) => self.clear_status(),
-            Err(err) => self.show_error(err),
+            Err(err) => {
+                let retry_op = err.retry_op();
+                self.show_error(err);
+                self.retry_op = retry_op;
+            }
         }
 
         Command::none()
     }
 
     fn subscription(&self) -> iced::Subscription<Message> {
-        self.jstation.subscription().map(Message::JStation)
+        iced::Subscription::batch([
+            self.jstation.subscription().map(Message::JStation),
+            self.controller.subscription().map(Message::Control),
+            iced::subscription::events_with(|event, status| {
+                use iced::keyboard::{self, KeyCode};
+
+                let iced::Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code,
+                    modifiers,
+                }) = event
+                else {
+                    return None;
+                };
+
+                // Ignore the keys used by the focused widget, e.g. a text input.
+                if status == iced::event::Status::Captured {
+                    return None;
+                }
+
+                match key_code {
+                    KeyCode::Z if modifiers.command() => Some(if modifiers.shift() {
+                        Message::Redo
+                    } else {
+                        Message::Undo
+                    }),
+                    KeyCode::Right | KeyCode::PageDown | KeyCode::Space => Some(Message::NextSong),
+                    KeyCode::Left | KeyCode::PageUp => Some(Message::PreviousSong),
+                    _ => None,
+                }
+            }),
+        ])
     }
 
     fn view(&self) -> Element<Message> {
@@ -288,7 +1269,7 @@ impl Application for App {
                 dsp = dsp.push(ui::delay::Panel::new(self.jstation.dsp().delay));
                 dsp = dsp.push(ui::reverb::Panel::new(self.jstation.dsp().reverb));
 
-                let progs = Column::with_children(
+                let progs = scrollable(Column::with_children(
                     ProgramNb::enumerate()
                         .map(|prog_nb| {
                             let prog_id = ProgramId::new(self.jstation.programs_bank(), prog_nb);
@@ -299,6 +1280,8 @@ impl Application for App {
                                 .map_or(false, |cur_prog_id| cur_prog_id == prog_id)
                             {
                                 ui::style::Button::ListItemSelected
+                            } else if self.jstation.is_empty_slot(prog_id) {
+                                ui::style::Button::ListItemEmpty
                             } else {
                                 ui::style::Button::ListItem
                             };
@@ -306,20 +1289,14 @@ impl Application for App {
                             iced::widget::Button::new(row![
                                 ui::value_label(prog_id.nb().to_string()),
                                 horizontal_space(Length::Fixed(5f32)),
-                                ui::value_label(
-                                    self.jstation
-                                        .get_program(prog_id)
-                                        .map_or("", Program::name)
-                                        .to_string()
-                                )
-                                .width(Length::Fill),
+                                ui::value_label(self.program_label(prog_id)).width(Length::Fill),
                             ])
                             .on_press(SelectProgram(prog_id))
                             .style(style.into())
                             .into()
                         })
                         .collect(),
-                );
+                ));
 
                 let mut left_header = row![
                     ui::button("Settings...")
@@ -330,40 +1307,135 @@ impl Application for App {
                         .on_press(ShowMidiConnection)
                         .style(style::Button::Default.into()),
                     horizontal_space(Length::Fixed(10f32)),
+                    ui::button("Controller...")
+                        .on_press(ShowController)
+                        .style(style::Button::Default.into()),
+                    horizontal_space(Length::Fixed(10f32)),
                     ui::button("Tuner...")
                         .on_press(ShowTuner)
                         .style(style::Button::Default.into()),
+                    horizontal_space(Length::Fixed(10f32)),
+                    ui::button("Blocks...")
+                        .on_press(ShowBlocks)
+                        .style(style::Button::Default.into()),
                     horizontal_space(Length::Fixed(20f32)),
                     ui::text_input("program name", self.jstation.dsp().name.as_str())
                         .on_input(Rename)
                         .width(Length::Fixed(300f32)),
                     horizontal_space(Length::Fill),
                 ]
-                .width(widget::DEFAULT_DSP_WIDTH);
+                .width(widget::DEFAULT_DSP_WIDTH)
+                .align_items(Alignment::Center);
 
-                if self.jstation.iface().is_connected() {
-                    if self.jstation.has_changed() {
-                        left_header = left_header.push(
-                            ui::button("Undo")
-                                .on_press(Undo)
-                                .style(style::Button::Default.into()),
-                        );
-                        left_header = left_header.push(horizontal_space(Length::Fixed(10f32)));
-                    }
+                if self.jstation.is_origin_unknown() {
+                    left_header = left_header.push(ui::value_label("unsaved / origin unknown"));
+                    left_header = left_header.push(horizontal_space(Length::Fixed(10f32)));
+                }
+
+                let mut undo = ui::button("Undo").style(style::Button::Default.into());
+                if self.jstation.can_undo() {
+                    undo = undo.on_press(Undo);
+                }
+                let mut redo = ui::button("Redo").style(style::Button::Default.into());
+                if self.jstation.can_redo() {
+                    redo = redo.on_press(Redo);
+                }
+                left_header = left_header
+                    .push(undo)
+                    .push(horizontal_space(Length::Fixed(5f32)))
+                    .push(redo)
+                    .push(horizontal_space(Length::Fixed(10f32)));
+
+                if self.jstation.has_changed() {
+                    let compare_style = if self.jstation.is_comparing() {
+                        style::Button::Active
+                    } else {
+                        style::Button::Default
+                    };
+                    left_header = left_header.push(
+                        ui::button("A/B")
+                            .on_press(Compare)
+                            .style(compare_style.into()),
+                    );
+                    left_header = left_header.push(horizontal_space(Length::Fixed(10f32)));
 
                     left_header = left_header.push(
-                        ui::button("Store...")
-                            .on_press(ShowStoreTo)
-                            .style(style::Button::Active.into()),
+                        ui::button("Revert")
+                            .on_press(Revert)
+                            .style(style::Button::Default.into()),
                     );
+                    left_header = left_header.push(horizontal_space(Length::Fixed(10f32)));
                 }
 
-                let right_header = row![ui::pick_list(
-                    PROGRAMS_BANKS.clone(),
-                    Some(self.jstation.programs_bank()),
-                    move |bank| { SelectProgramsBank(bank) }
-                )
-                .width(Length::Fill),];
+                if self.jstation.programs_bank().is_factory() {
+                    left_header = left_header.push(
+                        ui::button("Copy to user...")
+                            .on_press(ShowCopyToUser)
+                            .style(style::Button::Default.into()),
+                    );
+                    left_header = left_header.push(horizontal_space(Length::Fixed(10f32)));
+                }
+
+                left_header = left_header.push(
+                    ui::button("Store...")
+                        .on_press(ShowStoreTo)
+                        .style(style::Button::Active.into()),
+                );
+
+                let mut refresh = ui::button("Refresh").style(style::Button::Default.into());
+                if self.jstation.iface().is_connected()
+                    && self.jstation.bank_dump_progress().is_none()
+                    && self.jstation.fetch_progress().is_none()
+                {
+                    refresh = refresh.on_press(RefreshPrograms);
+                }
+
+                let library_style = if self.show_library {
+                    style::Button::Active
+                } else {
+                    style::Button::Default
+                };
+
+                let right_header = row![
+                    ui::pick_list(
+                        PROGRAMS_BANKS.clone(),
+                        Some(self.jstation.programs_bank()),
+                        SelectProgramsBank,
+                    )
+                    .width(Length::Fill),
+                    horizontal_space(Length::Fixed(10f32)),
+                    refresh,
+                    horizontal_space(Length::Fixed(10f32)),
+                    ui::button("Library")
+                        .on_press(ToggleLibrary)
+                        .style(library_style.into()),
+                    horizontal_space(Length::Fixed(10f32)),
+                    ui::button("Organize...")
+                        .on_press(ShowOrganizer)
+                        .style(style::Button::Default.into()),
+                    horizontal_space(Length::Fixed(10f32)),
+                    ui::button("Setlists...")
+                        .on_press(ShowSetlists)
+                        .style(style::Button::Default.into()),
+                    horizontal_space(Length::Fixed(10f32)),
+                    ui::button("File...")
+                        .on_press(ShowFile)
+                        .style(style::Button::Default.into()),
+                ];
+
+                let mut content = row![
+                    scrollable(dsp),
+                    horizontal_space(widget::DSP_PROGRAM_SPACING),
+                    progs,
+                ];
+                if self.show_library {
+                    content = content
+                        .push(horizontal_space(widget::DSP_PROGRAM_SPACING))
+                        .push(ui::library::list(
+                            self.library.as_ref(),
+                            &self.library_filter,
+                        ));
+                }
 
                 column![
                     row![
@@ -372,49 +1444,240 @@ impl Application for App {
                         right_header
                     ],
                     vertical_space(Length::Fixed(10f32)),
-                    row![
-                        scrollable(dsp),
-                        horizontal_space(widget::DSP_PROGRAM_SPACING),
-                        scrollable(progs),
-                    ],
+                    content,
                 ]
                 .into()
             }
-            Panel::StoreTo => {
-                let progs = scrollable(Column::with_children(
-                    ProgramNb::enumerate()
-                        .map(|prog_nb| {
-                            let prog_id = ProgramId::new_user(prog_nb);
+            Panel::Blocks => {
+                let programs = ProgramId::enumerate()
+                    .filter(|prog_id| !self.jstation.is_empty_slot(*prog_id))
+                    .filter_map(|prog_id| {
+                        let prog = self.jstation.get_program(prog_id)?;
+                        Some((prog_id, prog.name()))
+                    });
+
+                ui::modal_with_width(
+                    "Copy DSP block",
+                    ui::block::panel(
+                        &self.block_form,
+                        ui::block::Source::list(programs, self.library.as_ref()),
+                        self.clipboard.as_ref(),
+                    ),
+                    HideModal,
+                    400f32,
+                )
+                .into()
+            }
+            Panel::CopyToUser => {
+                let ui::factory::Form { factory, user } = self.factory_copy;
+                let factory_id = ProgramId::new_factory(factory);
+
+                let choices = ProgramNb::enumerate()
+                    .filter_map(|nb| {
+                        let prog = self.jstation.get_program(ProgramId::new_factory(nb))?;
+                        Some(ui::factory::Choice {
+                            nb,
+                            name: prog.name().to_string(),
+                        })
+                    })
+                    .collect();
 
-                            let style = if self
-                                .jstation
-                                .cur_prog_id()
-                                .map_or(false, |cur_prog_id| cur_prog_id.nb() == prog_id.nb())
-                            {
-                                ui::style::Button::ListItemSelected
-                            } else {
-                                ui::style::Button::ListItem
-                            };
+                let diffs = match (user, self.jstation.get_program(factory_id)) {
+                    (Some(user), Some(factory_prog)) => self.diff_list(
+                        user,
+                        |stored| diff::diff(stored, factory_prog.data()),
+                        ("User", "Factory"),
+                    ),
+                    (None, _) => ui::value_label("Select a user slot").into(),
+                    (_, None) => ui::value_label("Factory Program not loaded").into(),
+                };
+
+                ui::modal_with_width(
+                    "Copy factory Program to user slot",
+                    ui::factory::panel(
+                        self.factory_copy,
+                        choices,
+                        |prog_nb| self.program_label(ProgramId::new_user(prog_nb)),
+                        diffs,
+                    ),
+                    HideModal,
+                    ui::factory::SLOTS_WIDTH + DIFF_MODAL_WIDTH,
+                )
+                .into()
+            }
+            Panel::Organizer => ui::modal_with_width(
+                "Organize user bank",
+                ui::organizer::panel(
+                    &self.organizer,
+                    |prog_nb| self.program_label(ProgramId::new_user(prog_nb)),
+                    |prog_nb| {
+                        self.jstation
+                            .get_program(ProgramId::new_user(prog_nb))
+                            .is_some()
+                    },
+                ),
+                HideModal,
+                400f32,
+            )
+            .into(),
+            Panel::Setlists => {
+                let cur_song = self.jstation.cur_prog_id().map(|prog_id| jstation::Song {
+                    title: self.jstation.dsp().name.as_str().to_string(),
+                    prog_id,
+                    notes: String::new(),
+                });
+
+                ui::modal_with_width(
+                    "Setlists",
+                    ui::setlist::editor(&self.setlists, cur_song, |song| self.song_label(song)),
+                    HideModal,
+                    DIFF_MODAL_WIDTH,
+                )
+                .into()
+            }
+            Panel::Live => ui::setlist::live(&self.setlists, |song| self.song_label(song)),
+            Panel::StoreTo => {
+                ui::modal("Store to...", self.store_to_list(ConfirmStoreTo), HideModal).into()
+            }
+            Panel::ConfirmStoreTo(prog_nb) => self.confirm_store(
+                prog_nb,
+                |stored| diff::diff(stored, self.jstation.edit_buffer().data()),
+                StoreTo(prog_nb),
+            ),
+            Panel::LibraryEntry => match (self.library_entry(), self.library_form.as_ref()) {
+                (Some(entry), Some(form)) => {
+                    ui::modal("Library", ui::library::entry(entry, form), HideModal).into()
+                }
+                _ => ui::modal("Library", ui::value_label("Entry not found"), HideModal).into(),
+            },
+            Panel::RemoveLibraryEntry => match self.library_entry() {
+                Some(entry) => ui::modal(
+                    "Delete library entry?",
+                    ui::library::confirm_remove(entry),
+                    HideModal,
+                )
+                .into(),
+                None => ui::modal("Library", ui::value_label("Entry not found"), HideModal).into(),
+            },
+            Panel::LibraryStoreTo => ui::modal(
+                "Store to...",
+                self.store_to_list(ConfirmLibraryStoreTo),
+                HideModal,
+            )
+            .into(),
+            Panel::ConfirmLibraryStoreTo(prog_nb) => self.confirm_store(
+                prog_nb,
+                |stored| match self.library_entry() {
+                    Some(entry) => diff::diff(stored, entry.data()),
+                    None => Ok(Vec::new()),
+                },
+                LibraryStoreTo(prog_nb),
+            ),
+            Panel::File => {
+                let mut import_preset = ui::button("Import").style(style::Button::Default.into());
+                let mut export_preset = ui::button("Export").style(style::Button::Default.into());
+                let mut import_prog = ui::button("Import").style(style::Button::Default.into());
+                let mut export_prog = ui::button("Export").style(style::Button::Default.into());
+                let mut import_bank = ui::button("Import...").style(style::Button::Default.into());
+                let mut export_bank = ui::button("Export").style(style::Button::Default.into());
+                if !self.file_path.is_empty() {
+                    import_preset = import_preset.on_press(ImportPreset);
+                    export_preset = export_preset.on_press(ExportPreset);
+                    import_prog = import_prog.on_press(ImportProgram);
+                    export_prog = export_prog.on_press(ExportProgram);
+                    import_bank = import_bank.on_press(ImportBank);
+                    export_bank = export_bank.on_press(ExportBank);
+                }
 
-                            iced::widget::Button::new(row![
-                                ui::value_label(prog_id.nb().to_string()),
-                                horizontal_space(Length::Fixed(5f32)),
+                ui::modal(
+                    "File",
+                    column![
+                        ui::text_input("path/to/file", &self.file_path).on_input(FilePath),
+                        vertical_space(Length::Fixed(20f32)),
+                        row![
+                            ui::label("Preset (.toml)").width(Length::Fill),
+                            import_preset,
+                            horizontal_space(Length::Fixed(10f32)),
+                            export_preset,
+                        ]
+                        .align_items(Alignment::Center),
+                        vertical_space(Length::Fixed(10f32)),
+                        row![
+                            ui::label("Program (.syx)").width(Length::Fill),
+                            import_prog,
+                            horizontal_space(Length::Fixed(10f32)),
+                            export_prog,
+                        ]
+                        .align_items(Alignment::Center),
+                        vertical_space(Length::Fixed(10f32)),
+                        row![
+                            ui::label("Bank (.syx)").width(Length::Fill),
+                            import_bank,
+                            horizontal_space(Length::Fixed(10f32)),
+                            export_bank,
+                        ]
+                        .align_items(Alignment::Center),
+                    ]
+                    .align_items(Alignment::End),
+                    HideModal,
+                )
+                .into()
+            }
+            Panel::PendingBank => {
+                let progs = scrollable(Column::with_children(
+                    self.jstation
+                        .pending_bank()
+                        .iter()
+                        .map(|prog| {
+                            row![
+                                ui::value_label(prog.id().nb().to_string())
+                                    .width(Length::Fixed(30f32)),
+                                ui::value_label(prog.name()).width(Length::Fill),
                                 ui::value_label(
                                     self.jstation
-                                        .get_program(prog_id)
+                                        .get_program(prog.id())
                                         .map_or("", Program::name)
                                         .to_string()
                                 )
                                 .width(Length::Fill),
-                            ])
-                            .on_press(StoreTo(prog_id.nb()))
-                            .style(style.into())
+                            ]
                             .into()
                         })
                         .collect(),
-                ));
+                ))
+                .height(Length::Fixed(400f32));
 
-                ui::modal("Store to...", progs, HideModal).into()
+                let title = if self.jstation.iface().is_connected() {
+                    "Store Programs?"
+                } else {
+                    "Store Programs offline?"
+                };
+
+                ui::modal(
+                    title,
+                    column![
+                        row![
+                            ui::value_label("Nb").width(Length::Fixed(30f32)),
+                            ui::value_label("New").width(Length::Fill),
+                            ui::value_label("Replaces").width(Length::Fill),
+                        ],
+                        vertical_space(Length::Fixed(5f32)),
+                        progs,
+                        vertical_space(Length::Fixed(20f32)),
+                        row![
+                            ui::button("Discard")
+                                .on_press(DiscardPendingBank)
+                                .style(style::Button::Default.into()),
+                            horizontal_space(Length::Fixed(10f32)),
+                            ui::button("Store")
+                                .on_press(StorePendingBank)
+                                .style(style::Button::Active.into()),
+                        ],
+                    ]
+                    .align_items(Alignment::End),
+                    DiscardPendingBank,
+                )
+                .into()
             }
             Panel::MidiConnection => ui::modal(
                 "MIDI Connection",
@@ -429,6 +1692,19 @@ impl Application for App {
                 HideModal,
             )
             .into(),
+            Panel::Controller => ui::modal_with_width(
+                "MIDI Controller",
+                ui::controller::panel(
+                    &self.controller,
+                    self.controller
+                        .cur()
+                        .and_then(|port| self.setups.as_ref().and_then(|setups| setups.get(&port))),
+                    self.learn,
+                ),
+                HideModal,
+                400f32,
+            )
+            .into(),
             Panel::Tuner => ui::modal(
                 "Tuner On",
                 ui::button("Done")
@@ -451,12 +1727,7 @@ impl Application for App {
         let content: Element<_> = container(column![
             content,
             vertical_space(Length::Fill),
-            row![
-                Text::new(self.status_text.clone())
-                    .size(18)
-                    .width(Length::Fill),
-                ui::checkbox("Dark Theme", self.use_dark_them, UseDarkTheme),
-            ],
+            self.status_bar(),
         ])
         .padding(10)
         .width(Length::Fill)
@@ -476,18 +1747,83 @@ impl Application for App {
 #[derive(Debug, Clone)]
 pub enum Message {
     JStation(Result<jstation::Message, jstation::Error>),
+    /// The result of a device request.
+    Request(Result<(), jstation::Error>),
+    /// The result of a sync with the device: the unreadable `Program`s.
+    Synced(Result<Vec<ProgramId>, jstation::Error>),
+    /// The result of a store to the device: the stored `Program`s.
+    Stored(Result<Vec<Program>, jstation::Error>),
+    FilePath(String),
+    DiscardPendingBank,
+    ExportBank,
+    ExportPreset,
+    ExportProgram,
+    ImportBank,
+    ImportPreset,
+    ImportProgram,
+    AddToLibrary,
+    LibraryAuthor(String),
+    LibraryFilter(String),
+    LibraryNotes(String),
+    LibraryStoreTo(ProgramNb),
+    ConfirmLibraryStoreTo(ProgramNb),
+    LibraryTags(String),
+    RemoveLibraryEntry,
+    SaveLibraryEntry,
+    SelectLibraryEntry(PathBuf),
+    SendLibraryEntry,
+    ShowLibraryStoreTo,
+    ShowRemoveLibraryEntry,
+    ToggleLibrary,
     Parameter(dsp::Parameter),
     Midi(ui::midi::Selection),
+    ShowController,
+    ConnectController(Arc<str>),
+    DisconnectController,
+    Control((jstation::controller::Control, midi::CCValue)),
+    RemoveMapping(jstation::controller::Control),
+    StartLearn,
+    StopLearn,
+    RefreshPrograms,
     Rename(String),
     SelectProgram(ProgramId),
     SelectProgramsBank(ProgramsBank),
+    ShowBlocks,
+    SelectBlockKind(dsp::BlockKind),
+    SelectBlockSource(ui::block::Source),
+    CopyBlock,
+    PasteBlock,
+    SwapBlock,
+    ShowFile,
     ShowUtilitySettings,
     ShowMidiConnection,
     ShowStoreTo,
     ShowTuner,
     StartScan,
+    StorePendingBank,
+    ShowCopyToUser,
+    SelectCopySource(ProgramNb),
+    SelectCopyTarget(ProgramNb),
+    CopyToUser,
+    ShowOrganizer,
+    OrganizerAction(ui::organizer::Action),
+    OrganizerGrab(ProgramNb),
+    OrganizerDrop(ProgramNb),
+    ResetArrangement,
+    PreviewArrangement,
+    ShowSetlists,
+    Setlist(ui::setlist::Event),
+    GoLive,
+    NextSong,
+    PreviousSong,
+    ExitLive,
     StoreTo(ProgramNb),
+    ConfirmStoreTo(ProgramNb),
+    Revert,
+    Compare,
     Undo,
+    Redo,
+    Retry,
     HideModal,
     UseDarkTheme(bool),
     UtilitySettings(dsp::UtilitySettings),
@@ -555,8 +1891,21 @@ impl From<dsp::Parameter> for Message {
 enum Panel {
     #[default]
     Main,
+    Blocks,
+    File,
+    LibraryEntry,
+    RemoveLibraryEntry,
+    LibraryStoreTo,
+    ConfirmLibraryStoreTo(ProgramNb),
+    Organizer,
+    CopyToUser,
+    PendingBank,
+    Setlists,
+    Live,
     StoreTo,
+    ConfirmStoreTo(ProgramNb),
     MidiConnection,
+    Controller,
     Tuner,
     UtilitySettings,
 }
@@ -565,6 +1914,10 @@ impl Panel {
     fn is_tuner(self) -> bool {
         matches!(self, Panel::Tuner)
     }
+
+    fn is_live(self) -> bool {
+        matches!(self, Panel::Live)
+    }
 }
 
 #[derive(Clone, Debug, thiserror::Error)]
@@ -582,6 +1935,15 @@ pub enum Error {
     UnexpectedProgram(ProgramId),
 }
 
+impl Error {
+    fn retry_op(&self) -> Option<jstation::Operation> {
+        match self {
+            Error::JStation(err) => err.retry_op(),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, Hash)]
 pub enum Subscription {
     JStation,
@@ -0,0 +1,122 @@
+use std::{fmt, path::PathBuf};
+
+use iced::{
+    widget::{column, horizontal_space, row, vertical_space},
+    Alignment, Element, Length,
+};
+
+use crate::jstation::{
+    data::{dsp, ProgramId},
+    Library,
+};
+use crate::ui::{self, app::Message, style};
+
+/// Where a DSP block is copied from.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum Source {
+    #[default]
+    EditBuffer,
+    Program(ProgramId, String),
+    /// A library `Entry`, identified by its path.
+    Library(PathBuf, String),
+}
+
+impl Source {
+    /// Lists the available `Source`s: the edit buffer, the known `Program`s
+    /// and the library entries.
+    pub fn list<'a>(
+        programs: impl Iterator<Item = (ProgramId, &'a str)>,
+        library: Option<&Library>,
+    ) -> Vec<Source> {
+        let mut sources = vec![Source::EditBuffer];
+        sources.extend(programs.map(|(prog_id, name)| Source::Program(prog_id, name.to_string())));
+        if let Some(library) = library {
+            sources.extend(library.entries().iter().map(|entry| {
+                Source::Library(entry.path().to_path_buf(), entry.name().to_string())
+            }));
+        }
+
+        sources
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::EditBuffer => f.write_str("Edit buffer"),
+            Source::Program(prog_id, name) => write!(f, "{prog_id} {name}"),
+            Source::Library(_, name) => write!(f, "Library: {name}"),
+        }
+    }
+}
+
+/// A DSP block copied from a `Source`.
+#[derive(Clone, Debug)]
+pub struct Clipboard {
+    pub block: dsp::Block,
+    pub origin: String,
+}
+
+/// The DSP block to copy & its `Source`.
+#[derive(Clone, Debug, Default)]
+pub struct Form {
+    pub kind: dsp::BlockKind,
+    pub source: Source,
+}
+
+/// Builds the view to copy a DSP block from one of the `sources` & paste it.
+pub fn panel<'a>(
+    form: &Form,
+    sources: Vec<Source>,
+    clipboard: Option<&Clipboard>,
+) -> Element<'a, Message> {
+    let label_width = Length::Fixed(70f32);
+
+    let mut paste = ui::button("Paste").style(style::Button::Active.into());
+    let mut swap = ui::button("Swap").style(style::Button::Default.into());
+    let clipboard_label = match clipboard {
+        Some(clipboard) => {
+            paste = paste.on_press(Message::PasteBlock);
+            swap = swap.on_press(Message::SwapBlock);
+            format!("{} from {}", clipboard.block.kind(), clipboard.origin)
+        }
+        None => "Empty".to_string(),
+    };
+
+    column![
+        row![
+            ui::value_label("Block").width(label_width),
+            ui::pick_list(
+                &dsp::BlockKind::ALL[..],
+                Some(form.kind),
+                Message::SelectBlockKind
+            )
+            .width(Length::Fill),
+        ]
+        .align_items(Alignment::Center),
+        vertical_space(Length::Fixed(5f32)),
+        row![
+            ui::value_label("From").width(label_width),
+            ui::pick_list(
+                sources,
+                Some(form.source.clone()),
+                Message::SelectBlockSource
+            )
+            .width(Length::Fill),
+        ]
+        .align_items(Alignment::Center),
+        vertical_space(Length::Fixed(10f32)),
+        ui::button("Copy")
+            .on_press(Message::CopyBlock)
+            .style(style::Button::Default.into()),
+        vertical_space(Length::Fixed(20f32)),
+        row![
+            ui::value_label("Clipboard").width(label_width),
+            ui::value_label(clipboard_label).width(Length::Fill),
+        ],
+        vertical_space(Length::Fixed(20f32)),
+        row![swap, horizontal_space(Length::Fixed(10f32)), paste],
+    ]
+    .align_items(Alignment::End)
+    .into()
+}
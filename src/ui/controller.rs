@@ -0,0 +1,257 @@
+use iced::futures::{self, channel::mpsc, stream, StreamExt};
+use iced::{
+    widget::{column, horizontal_space, row, scrollable, vertical_space, Column},
+    Alignment, Element, Length,
+};
+use std::{borrow::Cow, cell::Cell, sync::Arc};
+
+use crate::{
+    jstation::{
+        self,
+        controller::{Control, Setup},
+        dsp,
+        transport::{Incoming, Midir},
+        Error,
+    },
+    midi,
+    ui::{self, app::Message, style},
+};
+
+/// The MIDI learn state.
+#[derive(Clone, Copy, Debug, Default)]
+pub enum Learn {
+    #[default]
+    Off,
+    /// Waiting for the user to touch a parameter in the UI.
+    AwaitingParameter,
+    /// Waiting for the user to move a `Control` for this parameter.
+    AwaitingControl(dsp::Parameter),
+}
+
+impl Learn {
+    pub fn is_on(self) -> bool {
+        !matches!(self, Learn::Off)
+    }
+}
+
+/// An external MIDI controller, connected to a second MIDI input port.
+pub struct Controller {
+    ins: midi::PortsIn,
+    incoming_tx: mpsc::UnboundedSender<Option<Incoming>>,
+    // Needs interior mutability because of subscription(&self)
+    incoming_rx: Cell<Option<mpsc::UnboundedReceiver<Option<Incoming>>>>,
+}
+
+impl Default for Controller {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Controller {
+    pub fn new() -> Self {
+        let (incoming_tx, incoming_rx) = mpsc::unbounded();
+
+        Controller {
+            ins: midi::PortsIn::new(crate::APP_NAME.clone()),
+            incoming_tx,
+            incoming_rx: Cell::new(Some(incoming_rx)),
+        }
+    }
+
+    pub fn refresh(&mut self) -> Result<(), Error> {
+        self.ins.refresh()?;
+
+        Ok(())
+    }
+
+    pub fn ports(&self) -> impl Iterator<Item = Arc<str>> + '_ {
+        self.ins.list()
+    }
+
+    /// Returns the name of the connected port.
+    pub fn cur(&self) -> Option<Arc<str>> {
+        self.ins.cur()
+    }
+
+    pub fn connect(&mut self, port_name: Arc<str>) -> Result<(), Error> {
+        let incoming = Midir::connect_in_unbounded(&mut self.ins, port_name)?;
+        self.incoming_tx
+            .unbounded_send(Some(incoming))
+            .map_err(|_| Error::ControllerSubscriptionClosed)
+    }
+
+    pub fn disconnect(&mut self) -> Result<(), Error> {
+        self.ins.disconnect();
+        self.incoming_tx
+            .unbounded_send(None)
+            .map_err(|_| Error::ControllerSubscriptionClosed)
+    }
+}
+
+/// iced Subscription helper.
+impl Controller {
+    /// Subscribes to the CCs received from the controller.
+    pub fn subscription(&self) -> iced::Subscription<(Control, midi::CCValue)> {
+        struct SubscriptionToken {
+            incoming: Incoming,
+            incoming_rx: mpsc::UnboundedReceiver<Option<Incoming>>,
+        }
+
+        async fn controller_subscription(
+            mut token: Option<SubscriptionToken>,
+        ) -> ((Control, midi::CCValue), Option<SubscriptionToken>) {
+            let SubscriptionToken {
+                ref mut incoming,
+                ref mut incoming_rx,
+            } = token
+                .as_mut()
+                .expect("token available while subscription is unfolded");
+
+            loop {
+                let msg = futures::select_biased! {
+                    incoming_opt = incoming_rx.next() => {
+                        match incoming_opt {
+                            Some(Some(new_incoming)) => {
+                                log::debug!("Got new controller connection");
+                                *incoming = new_incoming;
+                            }
+                            Some(None) => *incoming = Incoming::new(stream::pending()),
+                            None => {
+                                log::info!("Controller subscription channel closed");
+                                let () = futures::future::pending().await;
+                            }
+                        }
+
+                        continue;
+                    }
+                    msg = incoming.recv() => msg,
+                };
+
+                let Some(msg) = msg else {
+                    // Connection closed
+                    *incoming = Incoming::new(stream::pending());
+                    continue;
+                };
+
+                // Controllers also send other messages, e.g. clock or notes.
+                if let Ok((_, cv)) = midi::channel_voice::parse(&msg) {
+                    if let midi::channel_voice::Message::CC(cc) = cv.msg {
+                        let control = Control {
+                            chan: cv.chan,
+                            nb: cc.nb,
+                        };
+
+                        return ((control, cc.value), token);
+                    }
+                }
+            }
+        }
+
+        if let Some(incoming_rx) = self.incoming_rx.take() {
+            log::debug!("Spawning controller subscription");
+
+            return iced::subscription::unfold(
+                std::any::TypeId::of::<SubscriptionToken>(),
+                Some(SubscriptionToken {
+                    incoming: Incoming::new(stream::pending()),
+                    incoming_rx,
+                }),
+                controller_subscription,
+            );
+        }
+
+        // Keep subscription running
+        iced::subscription::unfold(
+            std::any::TypeId::of::<SubscriptionToken>(),
+            None,
+            controller_subscription,
+        )
+    }
+}
+
+/// Returns the label for the target parameter of `mapping`.
+fn target_label(mapping: &jstation::controller::Mapping) -> String {
+    match mapping.target_param() {
+        Some(param) => {
+            let (block, name) = param.names();
+            format!("{block} {name}")
+        }
+        None => format!("CC {}", mapping.target),
+    }
+}
+
+/// Builds the controller panel: the port & the `Mapping`s of its `Setup`.
+pub fn panel<'a>(
+    controller: &Controller,
+    setup: Option<&Setup>,
+    learn: Learn,
+) -> Element<'a, Message> {
+    let ports = Cow::from_iter(controller.ports());
+    let port_list =
+        ui::pick_list(ports, controller.cur(), Message::ConnectController).width(Length::Fill);
+
+    let mut disconnect = ui::button("Disconnect").style(style::Button::Default.into());
+    if controller.cur().is_some() {
+        disconnect = disconnect.on_press(Message::DisconnectController);
+    }
+
+    let mappings: Element<_> = match setup {
+        Some(setup) if !setup.mappings.is_empty() => scrollable(Column::with_children(
+            setup
+                .mappings
+                .iter()
+                .map(|mapping| {
+                    row![
+                        ui::value_label(format!(
+                            "Ch. {} CC {}",
+                            mapping.control.chan, mapping.control.nb,
+                        ))
+                        .width(Length::Fixed(120f32)),
+                        ui::value_label(target_label(mapping)).width(Length::Fill),
+                        ui::button("X")
+                            .on_press(Message::RemoveMapping(mapping.control))
+                            .style(style::Button::Default.into()),
+                    ]
+                    .spacing(5)
+                    .align_items(Alignment::Center)
+                    .into()
+                })
+                .collect(),
+        ))
+        .height(Length::Fixed(300f32))
+        .into(),
+        _ => ui::value_label("No mappings").into(),
+    };
+
+    let mut learn_btn = ui::button(if learn.is_on() {
+        "Learning..."
+    } else {
+        "Learn"
+    })
+    .style(style::Button::Active.into());
+    if controller.cur().is_some() {
+        learn_btn = learn_btn.on_press(Message::StartLearn);
+    }
+
+    column![
+        row![
+            ui::label("Controller"),
+            horizontal_space(Length::Fixed(10f32)),
+            port_list,
+            horizontal_space(Length::Fixed(10f32)),
+            disconnect,
+        ]
+        .align_items(Alignment::Center),
+        vertical_space(Length::Fixed(10f32)),
+        mappings,
+        vertical_space(Length::Fixed(20f32)),
+        row![
+            ui::value_label("Touch a parameter, then move a control"),
+            horizontal_space(Length::Fill),
+            learn_btn,
+        ]
+        .align_items(Alignment::Center),
+    ]
+    .into()
+}
@@ -0,0 +1,103 @@
+use std::fmt;
+
+use iced::{
+    widget::{column, horizontal_space, row, scrollable, vertical_space, Column},
+    Alignment, Element, Length,
+};
+
+use crate::jstation::ProgramNb;
+use crate::ui::{self, app::Message, style};
+
+/// Width of the factory `Program` & user slot selection column.
+pub const SLOTS_WIDTH: f32 = 270f32;
+
+/// A factory `Program` in the pick list.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Choice {
+    pub nb: ProgramNb,
+    pub name: String,
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.nb, self.name)
+    }
+}
+
+/// The factory `Program` to copy & the user slot it replaces.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Form {
+    pub factory: ProgramNb,
+    pub user: Option<ProgramNb>,
+}
+
+/// Builds the view to use a factory `Program` as the basis for a user slot.
+///
+/// `label` returns the label of the user `Program` stored in a slot
+/// & `diffs` lists the parameters the factory `Program` would change.
+pub fn panel<'a, 'l>(
+    form: Form,
+    choices: Vec<Choice>,
+    label: impl Fn(ProgramNb) -> &'l str,
+    diffs: Element<'a, Message>,
+) -> Element<'a, Message> {
+    let selected = choices
+        .iter()
+        .find(|choice| choice.nb == form.factory)
+        .cloned();
+
+    let slots = Column::with_children(
+        ProgramNb::enumerate()
+            .map(|nb| {
+                let style = if form.user == Some(nb) {
+                    style::Button::ListItemSelected
+                } else {
+                    style::Button::ListItem
+                };
+
+                iced::widget::Button::new(row![
+                    ui::value_label(nb).width(Length::Fixed(30f32)),
+                    ui::value_label(label(nb)).width(Length::Fill),
+                ])
+                .on_press(Message::SelectCopyTarget(nb))
+                .style(style.into())
+                .into()
+            })
+            .collect(),
+    );
+
+    let mut copy = ui::button("Copy").style(style::Button::Active.into());
+    if form.user.is_some() {
+        copy = copy.on_press(Message::CopyToUser);
+    }
+
+    column![
+        row![
+            column![
+                ui::value_label("Factory Program"),
+                vertical_space(Length::Fixed(5f32)),
+                ui::pick_list(choices, selected, |choice| Message::SelectCopySource(
+                    choice.nb
+                ))
+                .width(Length::Fill),
+                vertical_space(Length::Fixed(10f32)),
+                ui::value_label("Replaces user slot"),
+                vertical_space(Length::Fixed(5f32)),
+                scrollable(slots).height(Length::Fixed(350f32)),
+            ]
+            .width(Length::Fixed(SLOTS_WIDTH - 20f32)),
+            horizontal_space(Length::Fixed(20f32)),
+            column![diffs].width(Length::Fill),
+        ],
+        vertical_space(Length::Fixed(20f32)),
+        row![
+            ui::button("Cancel")
+                .on_press(Message::HideModal)
+                .style(style::Button::Default.into()),
+            horizontal_space(Length::Fixed(10f32)),
+            copy,
+        ],
+    ]
+    .align_items(Alignment::End)
+    .into()
+}
@@ -18,6 +18,12 @@ pub struct JStation {
     pending_listener: Option<Listener>,
 }
 
+impl Default for JStation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl JStation {
     pub fn new() -> Self {
         let (listener_tx, listener_rx) = mpsc::channel(1);
@@ -0,0 +1,143 @@
+use std::path::PathBuf;
+
+use iced::{
+    widget::{column, horizontal_space, row, scrollable, vertical_space, Column},
+    Alignment, Element, Length,
+};
+
+use crate::jstation::{library, preset::Metadata, Library};
+use crate::ui::{self, app::Message, style};
+
+/// The editable fields of a library `Entry`.
+#[derive(Clone, Debug)]
+pub struct Form {
+    pub path: PathBuf,
+    pub author: String,
+    pub tags: String,
+    pub notes: String,
+}
+
+impl Form {
+    pub fn new(entry: &library::Entry) -> Self {
+        Form {
+            path: entry.path().to_owned(),
+            author: entry.meta.author.clone(),
+            tags: entry.meta.tags.join(", "),
+            notes: entry.meta.notes.clone(),
+        }
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            author: self.author.trim().to_string(),
+            tags: Vec::from_iter(
+                self.tags
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(ToString::to_string),
+            ),
+            notes: self.notes.trim().to_string(),
+        }
+    }
+}
+
+/// Builds the list of library entries matching `filter`.
+pub fn list<'a>(library: Option<&Library>, filter: &str) -> Element<'a, Message> {
+    let Some(library) = library else {
+        return ui::value_label("Library unavailable").into();
+    };
+
+    let entries = Column::with_children(
+        library
+            .entries()
+            .iter()
+            .filter(|entry| entry.matches(filter))
+            .map(|entry| {
+                iced::widget::Button::new(ui::value_label(entry.name()).width(Length::Fill))
+                    .on_press(Message::SelectLibraryEntry(entry.path().to_owned()))
+                    .style(style::Button::ListItem.into())
+                    .into()
+            })
+            .collect(),
+    );
+
+    column![
+        ui::text_input("filter", filter).on_input(Message::LibraryFilter),
+        vertical_space(Length::Fixed(5f32)),
+        ui::button("Add current")
+            .on_press(Message::AddToLibrary)
+            .style(style::Button::Default.into()),
+        vertical_space(Length::Fixed(5f32)),
+        scrollable(entries),
+    ]
+    .into()
+}
+
+/// Builds the view for the library `Entry` being edited in `form`.
+pub fn entry<'a>(entry: &library::Entry, form: &Form) -> Element<'a, Message> {
+    let label_width = Length::Fixed(70f32);
+
+    column![
+        ui::label(entry.name().to_string()),
+        vertical_space(Length::Fixed(10f32)),
+        row![
+            ui::value_label("Author").width(label_width),
+            ui::text_input("author", &form.author).on_input(Message::LibraryAuthor),
+        ]
+        .align_items(Alignment::Center),
+        vertical_space(Length::Fixed(5f32)),
+        row![
+            ui::value_label("Tags").width(label_width),
+            ui::text_input("tag 1, tag 2", &form.tags).on_input(Message::LibraryTags),
+        ]
+        .align_items(Alignment::Center),
+        vertical_space(Length::Fixed(5f32)),
+        row![
+            ui::value_label("Notes").width(label_width),
+            ui::text_input("notes", &form.notes).on_input(Message::LibraryNotes),
+        ]
+        .align_items(Alignment::Center),
+        vertical_space(Length::Fixed(20f32)),
+        row![
+            ui::button("Delete...")
+                .on_press(Message::ShowRemoveLibraryEntry)
+                .style(style::Button::Default.into()),
+            horizontal_space(Length::Fixed(10f32)),
+            ui::button("Save")
+                .on_press(Message::SaveLibraryEntry)
+                .style(style::Button::Default.into()),
+            horizontal_space(Length::Fill),
+            ui::button("Load")
+                .on_press(Message::SendLibraryEntry)
+                .style(style::Button::Default.into()),
+            horizontal_space(Length::Fixed(10f32)),
+            ui::button("Store...")
+                .on_press(Message::ShowLibraryStoreTo)
+                .style(style::Button::Active.into()),
+        ],
+    ]
+    .align_items(Alignment::Start)
+    .into()
+}
+
+/// Asks for confirmation before deleting `entry` from the disk.
+pub fn confirm_remove<'a>(entry: &library::Entry) -> Element<'a, Message> {
+    column![
+        ui::label(entry.name().to_string()),
+        vertical_space(Length::Fixed(10f32)),
+        ui::value_label("The entry file will be deleted."),
+        vertical_space(Length::Fixed(20f32)),
+        row![
+            ui::button("Cancel")
+                .on_press(Message::HideModal)
+                .style(style::Button::Default.into()),
+            horizontal_space(Length::Fixed(10f32)),
+            ui::button("Delete")
+                .on_press(Message::RemoveLibraryEntry)
+                .style(style::Button::Active.into()),
+        ],
+    ]
+    .align_items(Alignment::End)
+    .into()
+}
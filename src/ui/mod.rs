@@ -3,23 +3,32 @@ pub mod style;
 pub mod widget;
 pub use widget::{
     amp_cabinet_label, button, checkbox, dsp, dsp_keep_width, hslider, knob, label, modal,
-    pick_list, radio, settings_checkbox, switch, text_input, toggler, value_label,
+    modal_with_width, pick_list, radio, settings_checkbox, switch, text_input, toggler,
+    value_label,
 };
 
 pub mod amp;
+pub mod block;
 pub mod cabinet;
 pub mod compressor;
 pub mod delay;
 pub mod effect;
+pub mod factory;
 pub mod noise_gate;
 pub mod reverb;
+pub mod sweep;
 pub mod utility_settings;
 pub mod wah_expr;
 
+pub mod library;
+pub mod organizer;
+pub mod setlist;
+
 pub mod app;
 pub use app::{App, APP_NAME};
 
 pub mod jstation;
 use jstation::JStation;
 
+pub mod controller;
 pub mod midi;
@@ -0,0 +1,198 @@
+use std::fmt;
+
+use iced::{
+    widget::{
+        column, container, horizontal_space, mouse_area::MouseArea, row, scrollable,
+        vertical_space, Column,
+    },
+    Alignment, Element, Length,
+};
+
+use crate::jstation::{Arrangement, ProgramNb};
+use crate::ui::{self, app::Message, style};
+
+/// What happens when a slot is dropped on another one.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum Action {
+    #[default]
+    Move,
+    Swap,
+    Duplicate,
+}
+
+impl Action {
+    pub const ALL: [Action; 3] = [Action::Move, Action::Swap, Action::Duplicate];
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Action::Move => "Move",
+            Action::Swap => "Swap",
+            Action::Duplicate => "Duplicate",
+        })
+    }
+}
+
+/// The bank `Arrangement` being edited.
+#[derive(Clone, Debug, Default)]
+pub struct Form {
+    pub arrangement: Arrangement,
+    pub action: Action,
+    /// The slot picked up, which will be dropped on the next selected slot.
+    pub picked: Option<ProgramNb>,
+}
+
+impl Form {
+    /// Picks up the slot `nb` when the mouse is pressed on it.
+    ///
+    /// If a slot was already picked, e.g. by a click, it is dropped on `nb`
+    /// & picking it again releases it.
+    ///
+    /// `is_known` tells whether the user `Program` in a slot is known.
+    /// Returns `false` if the action was refused, see [`Self::drop_on`].
+    pub fn grab(&mut self, nb: ProgramNb, is_known: impl Fn(ProgramNb) -> bool) -> bool {
+        match self.picked.take() {
+            None if is_known(self.arrangement.source(nb)) => self.picked = Some(nb),
+            None => return false,
+            Some(picked) if picked == nb => (),
+            Some(picked) => return self.apply(picked, nb, is_known),
+        }
+
+        true
+    }
+
+    /// Drops the picked slot on `nb` when the mouse is released on it.
+    ///
+    /// Returns `false` if the action was refused: the `Program`s which would
+    /// be moved must be known, otherwise they couldn't be stored.
+    pub fn drop_on(&mut self, nb: ProgramNb, is_known: impl Fn(ProgramNb) -> bool) -> bool {
+        match self.picked {
+            // Released on the slot it was picked from: wait for a click on the target.
+            Some(picked) if picked != nb => {
+                self.picked = None;
+                self.apply(picked, nb, is_known)
+            }
+            _ => true,
+        }
+    }
+
+    fn apply(
+        &mut self,
+        from: ProgramNb,
+        to: ProgramNb,
+        is_known: impl Fn(ProgramNb) -> bool,
+    ) -> bool {
+        let moved = match self.action {
+            // The slots in between are shifted.
+            Action::Move => Vec::from_iter(
+                ProgramNb::enumerate().filter(|nb| *nb >= from.min(to) && *nb <= from.max(to)),
+            ),
+            Action::Swap => vec![from, to],
+            // The target is overwritten.
+            Action::Duplicate => vec![from],
+        };
+
+        if !moved
+            .into_iter()
+            .all(|nb| is_known(self.arrangement.source(nb)))
+        {
+            return false;
+        }
+
+        match self.action {
+            Action::Move => self.arrangement.move_to(from, to),
+            Action::Swap => self.arrangement.swap(from, to),
+            Action::Duplicate => self.arrangement.duplicate(from, to),
+        }
+
+        true
+    }
+}
+
+/// Builds the view of the user bank as arranged in `form`.
+///
+/// A slot is picked by pressing the mouse on it & dropped by releasing
+/// the mouse on another slot, or by clicking it.
+///
+/// `label` returns the label of the `Program` currently stored in a slot
+/// & `is_known` whether that `Program` is known. Unknown `Program`s can't
+/// be picked, they can only be overwritten.
+pub fn panel<'a, 'l>(
+    form: &Form,
+    label: impl Fn(ProgramNb) -> &'l str,
+    is_known: impl Fn(ProgramNb) -> bool,
+) -> Element<'a, Message> {
+    let slots = Column::with_children(
+        ProgramNb::enumerate()
+            .map(|nb| {
+                let source = form.arrangement.source(nb);
+                let origin = if source != nb {
+                    format!("from {source}")
+                } else {
+                    String::new()
+                };
+
+                let is_known = is_known(source);
+                let style = if form.picked == Some(nb) {
+                    style::Button::ListItemSelected
+                } else if is_known {
+                    style::Button::ListItem
+                } else {
+                    style::Button::ListItemEmpty
+                };
+
+                let slot = container(row![
+                    ui::value_label(nb).width(Length::Fixed(30f32)),
+                    ui::value_label(label(source)).width(Length::Fill),
+                    ui::value_label(origin).width(Length::Fixed(70f32)),
+                ])
+                .padding([5, 10])
+                .width(Length::Fill)
+                .style(style);
+
+                let mut slot = MouseArea::new(slot).on_release(Message::OrganizerDrop(nb));
+                if is_known || form.picked.is_some() {
+                    slot = slot.on_press(Message::OrganizerGrab(nb));
+                }
+
+                slot.into()
+            })
+            .collect(),
+    );
+
+    let hint = match form.picked {
+        Some(picked) => format!("{} {picked} to...", form.action),
+        None => "Select a slot".to_string(),
+    };
+
+    let mut preview = ui::button("Preview...").style(style::Button::Active.into());
+    if form.arrangement.is_modified() {
+        preview = preview.on_press(Message::PreviewArrangement);
+    }
+
+    column![
+        row![
+            ui::pick_list(
+                &Action::ALL[..],
+                Some(form.action),
+                Message::OrganizerAction
+            ),
+            horizontal_space(Length::Fixed(10f32)),
+            ui::value_label(hint).width(Length::Fill),
+        ]
+        .align_items(Alignment::Center),
+        vertical_space(Length::Fixed(10f32)),
+        scrollable(slots).height(Length::Fixed(400f32)),
+        vertical_space(Length::Fixed(20f32)),
+        row![
+            ui::button("Reset")
+                .on_press(Message::ResetArrangement)
+                .style(style::Button::Default.into()),
+            horizontal_space(Length::Fixed(10f32)),
+            preview,
+        ],
+    ]
+    .align_items(Alignment::End)
+    .into()
+}
@@ -0,0 +1,397 @@
+use std::fmt;
+
+use iced::{
+    alignment::Horizontal,
+    widget::{column, container, horizontal_space, row, scrollable, text, vertical_space, Column},
+    Alignment, Element, Length,
+};
+
+use crate::jstation::{Error, Setlist, Setlists, Song};
+use crate::ui::{self, app::Message, style};
+
+/// A `Setlist` in the pick list.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Choice {
+    pub idx: usize,
+    pub name: String,
+}
+
+impl fmt::Display for Choice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Event {
+    Select(usize),
+    New,
+    Remove,
+    Rename(String),
+    AddSong(Song),
+    SelectSong(usize),
+    SongTitle(String),
+    SongNotes(String),
+    /// Saves the text edits.
+    Commit,
+    MoveSongUp(usize),
+    MoveSongDown(usize),
+    RemoveSong(usize),
+}
+
+impl From<Event> for Message {
+    fn from(evt: Event) -> Self {
+        Message::Setlist(evt)
+    }
+}
+
+/// The `Setlists` & the selections in the editor & the live view.
+#[derive(Debug, Default)]
+pub struct State {
+    pub setlists: Option<Setlists>,
+    pub selected: usize,
+    /// The `Song` being edited.
+    pub song: Option<usize>,
+    /// The `Song` being played in the live view.
+    pub live_song: usize,
+    /// Whether text edits are yet to be saved.
+    is_dirty: bool,
+}
+
+impl State {
+    pub fn setlist(&self) -> Option<&Setlist> {
+        self.setlists.as_ref()?.lists.get(self.selected)
+    }
+
+    /// Returns the `Song` being played in the live view.
+    pub fn live_song(&self) -> Option<&Song> {
+        self.setlist()?.songs.get(self.live_song)
+    }
+
+    /// Steps `offset` `Song`s in the live view.
+    ///
+    /// Returns the new `Song` unless the end of the `Setlist` was reached.
+    pub fn step(&mut self, offset: isize) -> Option<&Song> {
+        let len = self.setlist()?.songs.len();
+        let idx = self.live_song.checked_add_signed(offset)?;
+        if idx >= len {
+            return None;
+        }
+
+        self.live_song = idx;
+        self.live_song()
+    }
+
+    /// Saves the `Setlists` if text edits are pending.
+    pub fn commit(&mut self) -> Result<(), Error> {
+        let Some(setlists) = self.setlists.as_ref() else {
+            return Ok(());
+        };
+
+        if !self.is_dirty {
+            return Ok(());
+        }
+
+        self.is_dirty = false;
+        setlists.save()
+    }
+
+    pub fn update(&mut self, evt: Event) -> Result<(), Error> {
+        use Event::*;
+
+        let Some(setlists) = self.setlists.as_mut() else {
+            return Ok(());
+        };
+
+        match evt {
+            Select(idx) => {
+                self.selected = idx;
+                self.song = None;
+                self.live_song = 0;
+                return Ok(());
+            }
+            SelectSong(idx) => {
+                self.song = Some(idx);
+                return Ok(());
+            }
+            Commit => return self.commit(),
+            New => {
+                setlists.lists.push(Setlist {
+                    name: format!("Setlist {}", setlists.lists.len() + 1),
+                    songs: Vec::new(),
+                });
+                self.selected = setlists.lists.len() - 1;
+                self.song = None;
+            }
+            Remove => {
+                if self.selected < setlists.lists.len() {
+                    setlists.lists.remove(self.selected);
+                }
+                self.selected = 0;
+                self.song = None;
+            }
+            evt => {
+                let Some(setlist) = setlists.lists.get_mut(self.selected) else {
+                    return Ok(());
+                };
+
+                match evt {
+                    // Text edits are saved on commit, not on each keystroke.
+                    Rename(name) => {
+                        setlist.name = name;
+                        self.is_dirty = true;
+                        return Ok(());
+                    }
+                    AddSong(song) => {
+                        setlist.songs.push(song);
+                        self.song = Some(setlist.songs.len() - 1);
+                    }
+                    SongTitle(title) => {
+                        if let Some(song) = self.song.and_then(|idx| setlist.songs.get_mut(idx)) {
+                            song.title = title;
+                            self.is_dirty = true;
+                        }
+
+                        return Ok(());
+                    }
+                    SongNotes(notes) => {
+                        if let Some(song) = self.song.and_then(|idx| setlist.songs.get_mut(idx)) {
+                            song.notes = notes;
+                            self.is_dirty = true;
+                        }
+
+                        return Ok(());
+                    }
+                    MoveSongUp(idx) if idx > 0 => {
+                        setlist.songs.swap(idx - 1, idx);
+                        self.song = Some(idx - 1);
+                    }
+                    MoveSongDown(idx) if idx + 1 < setlist.songs.len() => {
+                        setlist.songs.swap(idx, idx + 1);
+                        self.song = Some(idx + 1);
+                    }
+                    RemoveSong(idx) if idx < setlist.songs.len() => {
+                        setlist.songs.remove(idx);
+                        self.song = None;
+                    }
+                    _ => return Ok(()),
+                }
+            }
+        }
+
+        self.is_dirty = false;
+        setlists.save()
+    }
+}
+
+/// Builds the `Setlist` editor.
+///
+/// `cur_song` is the `Song` for the current `Program`, if any,
+/// & `label` returns the label for the `Program` of a `Song`.
+pub fn editor<'a>(
+    state: &State,
+    cur_song: Option<Song>,
+    label: impl Fn(&Song) -> String,
+) -> Element<'a, Message> {
+    let Some(setlists) = state.setlists.as_ref() else {
+        return ui::value_label("Setlists unavailable").into();
+    };
+
+    let choices = Vec::from_iter(
+        setlists
+            .lists
+            .iter()
+            .enumerate()
+            .map(|(idx, setlist)| Choice {
+                idx,
+                name: setlist.name.clone(),
+            }),
+    );
+    let selected = choices.get(state.selected).cloned();
+
+    let header = row![
+        ui::pick_list(choices, selected, |choice| Event::Select(choice.idx).into())
+            .width(Length::Fill),
+        horizontal_space(Length::Fixed(10f32)),
+        ui::button("New")
+            .on_press(Event::New.into())
+            .style(style::Button::Default.into()),
+    ]
+    .align_items(Alignment::Center);
+
+    let Some(setlist) = state.setlist() else {
+        return header.into();
+    };
+
+    let songs = Column::with_children(
+        setlist
+            .songs
+            .iter()
+            .enumerate()
+            .map(|(idx, song)| {
+                let style = if state.song == Some(idx) {
+                    style::Button::ListItemSelected
+                } else {
+                    style::Button::ListItem
+                };
+
+                row![
+                    iced::widget::Button::new(row![
+                        ui::value_label(idx + 1).width(Length::Fixed(25f32)),
+                        ui::value_label(&song.title).width(Length::Fill),
+                        ui::value_label(label(song)).width(Length::Fill),
+                    ])
+                    .on_press(Event::SelectSong(idx).into())
+                    .style(style.into())
+                    .width(Length::Fill),
+                    ui::button("Up")
+                        .on_press(Event::MoveSongUp(idx).into())
+                        .style(style::Button::Default.into()),
+                    ui::button("Down")
+                        .on_press(Event::MoveSongDown(idx).into())
+                        .style(style::Button::Default.into()),
+                    ui::button("X")
+                        .on_press(Event::RemoveSong(idx).into())
+                        .style(style::Button::Default.into()),
+                ]
+                .spacing(5)
+                .align_items(Alignment::Center)
+                .into()
+            })
+            .collect(),
+    );
+
+    let label_width = Length::Fixed(50f32);
+    let song_form: Element<_> = match state.song.and_then(|idx| setlist.songs.get(idx)) {
+        Some(song) => column![
+            row![
+                ui::value_label("Title").width(label_width),
+                ui::text_input("title", &song.title)
+                    .on_input(|title| Event::SongTitle(title).into())
+                    .on_submit(Event::Commit.into()),
+            ]
+            .align_items(Alignment::Center),
+            vertical_space(Length::Fixed(5f32)),
+            row![
+                ui::value_label("Notes").width(label_width),
+                ui::text_input("notes", &song.notes)
+                    .on_input(|notes| Event::SongNotes(notes).into())
+                    .on_submit(Event::Commit.into()),
+            ]
+            .align_items(Alignment::Center),
+        ]
+        .into(),
+        None => ui::value_label("Select a song to edit it").into(),
+    };
+
+    let mut add_song = ui::button("Add current Program").style(style::Button::Default.into());
+    if let Some(song) = cur_song {
+        add_song = add_song.on_press(Event::AddSong(song).into());
+    }
+
+    let mut go_live = ui::button("Go live").style(style::Button::Active.into());
+    if !setlist.songs.is_empty() {
+        go_live = go_live.on_press(Message::GoLive);
+    }
+
+    column![
+        header,
+        vertical_space(Length::Fixed(10f32)),
+        row![
+            ui::value_label("Name").width(label_width),
+            ui::text_input("setlist name", &setlist.name)
+                .on_input(|name| Event::Rename(name).into())
+                .on_submit(Event::Commit.into()),
+        ]
+        .align_items(Alignment::Center),
+        vertical_space(Length::Fixed(10f32)),
+        scrollable(songs).height(Length::Fixed(300f32)),
+        vertical_space(Length::Fixed(10f32)),
+        song_form,
+        vertical_space(Length::Fixed(20f32)),
+        row![
+            ui::button("Delete setlist")
+                .on_press(Event::Remove.into())
+                .style(style::Button::Default.into()),
+            horizontal_space(Length::Fill),
+            add_song,
+            horizontal_space(Length::Fixed(10f32)),
+            go_live,
+        ],
+    ]
+    .into()
+}
+
+/// Builds the full screen live view for the current & the next `Song`.
+///
+/// `label` returns the label for the `Program` of a `Song`.
+pub fn live<'a>(state: &State, label: impl Fn(&Song) -> String) -> Element<'a, Message> {
+    let Some(setlist) = state.setlist() else {
+        return ui::value_label("No setlist").into();
+    };
+
+    let big_text = |content: String, size: u16| {
+        text(content)
+            .size(size)
+            .width(Length::Fill)
+            .horizontal_alignment(Horizontal::Center)
+    };
+
+    let cur: Element<_> = match state.live_song() {
+        Some(song) => column![
+            big_text(song.title.clone(), 96),
+            big_text(label(song), 48),
+            vertical_space(Length::Fixed(20f32)),
+            big_text(song.notes.clone(), 32),
+        ]
+        .into(),
+        None => big_text("End".to_string(), 96).into(),
+    };
+
+    let next = match setlist.songs.get(state.live_song + 1) {
+        Some(song) => format!("Next: {}", song.title),
+        None => "Last song".to_string(),
+    };
+
+    column![
+        row![
+            ui::label(format!(
+                "{}  {}/{}",
+                setlist.name,
+                state.live_song + 1,
+                setlist.songs.len(),
+            ))
+            .width(Length::Fill),
+            ui::button("Exit")
+                .on_press(Message::ExitLive)
+                .style(style::Button::Default.into()),
+        ]
+        .align_items(Alignment::Center),
+        // A click anywhere on the current song switches to the next one.
+        iced::widget::Button::new(
+            container(cur)
+                .width(Length::Fill)
+                .height(Length::Fill)
+                .center_y(),
+        )
+        .on_press(Message::NextSong)
+        .style(style::Button::ListItem.into())
+        .width(Length::Fill)
+        .height(Length::Fill),
+        big_text(next, 48),
+        vertical_space(Length::Fixed(20f32)),
+        row![
+            iced::widget::Button::new(big_text("Previous".to_string(), 32))
+                .on_press(Message::PreviousSong)
+                .style(style::Button::Default.into())
+                .width(Length::Fill),
+            horizontal_space(Length::Fixed(20f32)),
+            iced::widget::Button::new(big_text("Next".to_string(), 32))
+                .on_press(Message::NextSong)
+                .style(style::Button::Active.into())
+                .width(Length::Fill),
+        ],
+    ]
+    .padding(20)
+    .into()
+}
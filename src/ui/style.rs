@@ -33,6 +33,7 @@ pub enum Button {
     ModalClose,
     ListItem,
     ListItemSelected,
+    ListItemEmpty,
 }
 
 impl button::StyleSheet for Button {
@@ -73,6 +74,14 @@ impl button::StyleSheet for Button {
                 },
                 ..appearance
             },
+            ListItemEmpty => button::Appearance {
+                text_color: match style {
+                    iced::Theme::Dark => Color::from_rgb(0.4, 0.4, 0.45),
+                    iced::Theme::Light => Color::from_rgb(0.7, 0.65, 0.65),
+                    _ => appearance.text_color,
+                },
+                ..ListItem.active(style)
+            },
             ListItemSelected | Active => button::Appearance {
                 background: Some(iced::Background::Color(Color::from_rgb(0.55, 0.0, 0.0))),
                 text_color: Color::from_rgb(0.9, 0.9, 0.95),
@@ -125,6 +134,7 @@ impl button::StyleSheet for Button {
                 },
                 ..appearance
             },
+            ListItemEmpty => ListItem.hovered(style),
             ListItemSelected | Active => button::Appearance {
                 background: Some(iced::Background::Color(Color::from_rgb(0.75, 0.0, 0.0))),
                 text_color: Color::WHITE,
@@ -158,6 +168,28 @@ impl From<Button> for iced::theme::Button {
     }
 }
 
+/// Uses the `Button` styles for containers, e.g. list items which are dragged.
+impl container::StyleSheet for Button {
+    type Style = iced::Theme;
+
+    fn appearance(&self, style: &Self::Style) -> container::Appearance {
+        let appearance = button::StyleSheet::active(self, style);
+        container::Appearance {
+            text_color: Some(appearance.text_color),
+            background: appearance.background,
+            border_radius: appearance.border_radius,
+            border_width: appearance.border_width,
+            border_color: appearance.border_color,
+        }
+    }
+}
+
+impl From<Button> for iced::theme::Container {
+    fn from(style: Button) -> Self {
+        iced::theme::Container::Custom(Box::new(style))
+    }
+}
+
 pub struct Checkbox;
 
 impl Checkbox {
@@ -0,0 +1,284 @@
+use iced::{
+    mouse,
+    widget::canvas::{self, event, Cursor, Event, Frame, Geometry, Path, Stroke},
+    Color, Length, Point, Rectangle, Theme,
+};
+
+use crate::jstation::data::Normal;
+
+/// Padding so that the end points are fully drawn.
+const PADDING: f32 = 6f32;
+const END_POINT_RADIUS: f32 = 5f32;
+
+/// An end point of a `Sweep`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EndPoint {
+    /// The value when the pedal is back, e.g. the heel.
+    Back,
+    /// The value when the pedal is forward, e.g. the toe.
+    Forward,
+}
+
+/// A graph of how a param sweeps between its `Back` & `Forward` values
+/// as the pedal moves, with the live pedal position.
+///
+/// The end points can be dragged vertically.
+pub struct Sweep<OnChange> {
+    back: Normal,
+    forward: Normal,
+    position: Option<Normal>,
+    on_change: OnChange,
+}
+
+/// Builds a `Sweep` canvas.
+///
+/// `on_change` is called with the new value of an `EndPoint` while it is dragged.
+pub fn sweep<Message, OnChange>(
+    back: Normal,
+    forward: Normal,
+    position: Option<Normal>,
+    on_change: OnChange,
+) -> canvas::Canvas<Message, Theme, Sweep<OnChange>>
+where
+    OnChange: Fn(EndPoint, Normal) -> Message,
+{
+    iced::widget::canvas(Sweep {
+        back,
+        forward,
+        position,
+        on_change,
+    })
+    .width(Length::Fixed(90f32))
+    .height(Length::Fixed(70f32))
+}
+
+/// The `EndPoint` being dragged, if any.
+#[derive(Default)]
+pub struct State {
+    dragging: Option<EndPoint>,
+}
+
+impl<OnChange> Sweep<OnChange> {
+    fn x(bounds: Rectangle, ratio: f32) -> f32 {
+        PADDING + ratio * (bounds.width - 2f32 * PADDING)
+    }
+
+    fn y(bounds: Rectangle, ratio: f32) -> f32 {
+        bounds.height - PADDING - ratio * (bounds.height - 2f32 * PADDING)
+    }
+
+    fn end_point(&self, bounds: Rectangle, end_point: EndPoint) -> Point {
+        match end_point {
+            EndPoint::Back => Point::new(Self::x(bounds, 0f32), Self::y(bounds, self.back.into())),
+            EndPoint::Forward => {
+                Point::new(Self::x(bounds, 1f32), Self::y(bounds, self.forward.into()))
+            }
+        }
+    }
+
+    /// Returns the `EndPoint` closest to `pos`, which is relative to `bounds`.
+    fn closest(&self, bounds: Rectangle, pos: Point) -> EndPoint {
+        let back = self.end_point(bounds, EndPoint::Back).distance(pos);
+        let forward = self.end_point(bounds, EndPoint::Forward).distance(pos);
+
+        if back <= forward {
+            EndPoint::Back
+        } else {
+            EndPoint::Forward
+        }
+    }
+
+    /// Returns the value for the vertical position `y`, which is relative to `bounds`.
+    fn value_at(bounds: Rectangle, y: f32) -> Normal {
+        let ratio = (bounds.height - PADDING - y) / (bounds.height - 2f32 * PADDING);
+
+        Normal::try_from(ratio.clamp(0f32, 1f32)).expect("clamped")
+    }
+}
+
+impl<Message, OnChange> canvas::Program<Message, Theme> for Sweep<OnChange>
+where
+    OnChange: Fn(EndPoint, Normal) -> Message,
+{
+    type State = State;
+
+    fn update(
+        &self,
+        state: &mut State,
+        event: Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        let Event::Mouse(event) = event else {
+            return (event::Status::Ignored, None);
+        };
+
+        match event {
+            mouse::Event::ButtonPressed(mouse::Button::Left) => {
+                let Some(pos) = cursor.position_in(&bounds) else {
+                    return (event::Status::Ignored, None);
+                };
+
+                let end_point = self.closest(bounds, pos);
+                state.dragging = Some(end_point);
+
+                (
+                    event::Status::Captured,
+                    Some((self.on_change)(end_point, Self::value_at(bounds, pos.y))),
+                )
+            }
+            mouse::Event::CursorMoved { .. } => match (state.dragging, cursor.position()) {
+                (Some(end_point), Some(pos)) => (
+                    event::Status::Captured,
+                    Some((self.on_change)(
+                        end_point,
+                        Self::value_at(bounds, pos.y - bounds.y),
+                    )),
+                ),
+                _ => (event::Status::Ignored, None),
+            },
+            mouse::Event::ButtonReleased(mouse::Button::Left) if state.dragging.is_some() => {
+                state.dragging = None;
+                (event::Status::Captured, None)
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+
+    fn draw(
+        &self,
+        state: &State,
+        theme: &Theme,
+        bounds: Rectangle,
+        _cursor: Cursor,
+    ) -> Vec<Geometry> {
+        let palette = theme.palette();
+        let mut frame = Frame::new(bounds.size());
+
+        let grid = Color {
+            a: 0.3,
+            ..palette.text
+        };
+        frame.stroke(
+            &Path::rectangle(
+                Point::new(PADDING, PADDING),
+                iced::Size::new(
+                    bounds.width - 2f32 * PADDING,
+                    bounds.height - 2f32 * PADDING,
+                ),
+            ),
+            Stroke::default().with_color(grid).with_width(1f32),
+        );
+
+        let back = self.end_point(bounds, EndPoint::Back);
+        let forward = self.end_point(bounds, EndPoint::Forward);
+        frame.stroke(
+            &Path::line(back, forward),
+            Stroke::default()
+                .with_color(palette.primary)
+                .with_width(2f32),
+        );
+
+        for (end_point, center) in [(EndPoint::Back, back), (EndPoint::Forward, forward)] {
+            let radius = if state.dragging == Some(end_point) {
+                END_POINT_RADIUS + 1f32
+            } else {
+                END_POINT_RADIUS
+            };
+            frame.fill(&Path::circle(center, radius), palette.primary);
+        }
+
+        if let Some(position) = self.position {
+            // The live pedal position and the resulting value.
+            let ratio = position.as_ratio();
+            let value =
+                self.back.as_ratio() + ratio * (self.forward.as_ratio() - self.back.as_ratio());
+            let x = Self::x(bounds, ratio);
+
+            frame.stroke(
+                &Path::line(
+                    Point::new(x, PADDING),
+                    Point::new(x, bounds.height - PADDING),
+                ),
+                Stroke::default().with_color(grid).with_width(1f32),
+            );
+            frame.fill(
+                &Path::circle(Point::new(x, Self::y(bounds, value)), 3f32),
+                palette.text,
+            );
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(
+        &self,
+        state: &State,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> mouse::Interaction {
+        if state.dragging.is_some() {
+            mouse::Interaction::Grabbing
+        } else if cursor.is_over(&bounds) {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use iced::{Point, Rectangle, Size};
+
+    use super::{EndPoint, Sweep, PADDING};
+    use crate::jstation::data::Normal;
+
+    fn bounds() -> Rectangle {
+        Rectangle::new(Point::new(10f32, 20f32), Size::new(90f32, 70f32))
+    }
+
+    #[test]
+    fn coordinates() {
+        assert_eq!(Sweep::<()>::x(bounds(), 0f32), PADDING);
+        assert_eq!(Sweep::<()>::x(bounds(), 1f32), 90f32 - PADDING);
+        assert_eq!(Sweep::<()>::y(bounds(), 0f32), 70f32 - PADDING);
+        assert_eq!(Sweep::<()>::y(bounds(), 1f32), PADDING);
+
+        for normal in [Normal::MIN, Normal::CENTER, Normal::MAX] {
+            let y = Sweep::<()>::y(bounds(), normal.as_ratio());
+            assert_eq!(Sweep::<()>::value_at(bounds(), y), normal);
+        }
+
+        // Out of the graph
+        assert_eq!(Sweep::<()>::value_at(bounds(), 0f32), Normal::MAX);
+        assert_eq!(Sweep::<()>::value_at(bounds(), 70f32), Normal::MIN);
+    }
+
+    #[test]
+    fn closest() {
+        let sweep = Sweep {
+            back: Normal::MIN,
+            forward: Normal::MAX,
+            position: None,
+            on_change: (),
+        };
+
+        assert_eq!(
+            sweep.closest(bounds(), Point::new(PADDING, 70f32 - PADDING)),
+            EndPoint::Back,
+        );
+        assert_eq!(
+            sweep.closest(bounds(), Point::new(90f32 - PADDING, PADDING)),
+            EndPoint::Forward,
+        );
+        assert_eq!(
+            sweep.closest(bounds(), Point::new(90f32 - PADDING, 70f32 - PADDING)),
+            EndPoint::Forward,
+        );
+        assert_eq!(
+            sweep.closest(bounds(), Point::new(PADDING, PADDING)),
+            EndPoint::Back,
+        );
+    }
+}
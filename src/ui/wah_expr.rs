@@ -1,16 +1,17 @@
 use iced::{
     widget::{column, horizontal_space, row, text, vertical_space},
-    Element, Length,
+    Alignment, Element, Length,
 };
 use iced_lazy::{self, Component};
 
 use crate::jstation::{
-    data::dsp::{
-        self, {expression, pedal, wah, Expression, Pedal, Wah},
+    data::{
+        dsp::{self, expression, pedal, wah, Expression, Pedal, Wah},
+        Normal,
     },
     prelude::*,
 };
-use crate::ui;
+use crate::ui::{self, sweep::EndPoint};
 
 pub struct Panel {
     expression: Expression,
@@ -95,6 +96,45 @@ where
             ]
         };
 
+        let (target, back, forward, on_change): (_, _, _, fn(EndPoint, Normal) -> dsp::Parameter) =
+            if self.wah.switch.is_true() {
+                (
+                    "Wah".to_string(),
+                    self.wah.heel.normal(),
+                    self.wah.toe.normal(),
+                    |end_point, normal| match end_point {
+                        EndPoint::Back => Wah(wah::Heel::from_normal(normal).into()),
+                        EndPoint::Forward => Wah(wah::Toe::from_normal(normal).into()),
+                    },
+                )
+            } else {
+                (
+                    self.expression.assignment.name().to_string(),
+                    self.expression.back.normal(),
+                    self.expression.forward.normal(),
+                    |end_point, normal| match end_point {
+                        EndPoint::Back => Expression(expression::Back::from_normal(normal).into()),
+                        EndPoint::Forward => {
+                            Expression(expression::Forward::from_normal(normal).into())
+                        }
+                    },
+                )
+            };
+        let sweep = ui::sweep::sweep(
+            back.unwrap_or_default(),
+            forward.unwrap_or_default(),
+            self.pedal.expression.normal(),
+            on_change,
+        );
+        pedal = pedal.push(
+            column![
+                sweep,
+                vertical_space(Length::Fixed(2f32)),
+                ui::value_label(target)
+            ]
+            .align_items(Alignment::Center),
+        );
+
         pedal = pedal.push(column![
             ui::label(if self.wah.switch.is_true() {
                 "Wah"
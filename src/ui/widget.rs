@@ -92,6 +92,19 @@ pub fn modal<'a, Message>(
     element: impl Into<Element<'a, Message, iced::Renderer>>,
     on_hide: Message,
 ) -> Container<'a, Message>
+where
+    Message: 'a + Clone,
+{
+    modal_with_width(title, element, on_hide, 350f32)
+}
+
+/// Builds a modal for content wider than the default, e.g. a list with several columns.
+pub fn modal_with_width<'a, Message>(
+    title: &str,
+    element: impl Into<Element<'a, Message, iced::Renderer>>,
+    on_hide: Message,
+    width: f32,
+) -> Container<'a, Message>
 where
     Message: 'a + Clone,
 {
@@ -110,7 +123,7 @@ where
             vertical_space(Length::Fixed(30f32)),
             container(element.into()).width(Length::Fill).center_x(),
         ]
-        .width(Length::Fixed(350f32)),
+        .width(Length::Fixed(width)),
     )
     .width(Length::Fill)
     .center_x()